@@ -1,15 +1,57 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use quadtree::{Point, QuadTree, Rectangle};
+use quadtree::{Point, QuadTree, QuadTreeBuilder, Rectangle};
 
 fn insert_point_in_quadtree(pt: Point) {
     let mut quadtree = QuadTree::new_bounded(&Rectangle::new((0.0, 0.0), 1000.0, 1000.0));
     quadtree.insert(0u8, pt);
 }
 
+/// A tree filled with a deterministic, scattered grid of points
+fn filled_quadtree(bucket_capacity: usize) -> QuadTree<usize> {
+    let bounds = Rectangle::new((0.0, 0.0), 1000.0, 1000.0);
+    let mut quadtree = QuadTreeBuilder::new(&bounds)
+        .bucket_capacity(bucket_capacity)
+        .build();
+    for i in 0..10_000usize {
+        let x = (i * 7919 % 10_007) as f32 / 10.007;
+        let y = (i * 104_729 % 10_009) as f32 / 10.009;
+        quadtree.insert(i, (x, y));
+    }
+    quadtree
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("pt insert 1", |b| {
         b.iter(|| insert_point_in_quadtree(black_box(Point::new(20.0, 50.0))))
     });
+
+    // one point per leaf, against buckets of points
+    let query = Rectangle::new((200.0, 300.0), 250.0, 180.0);
+    let center = Point::new(500.0, 500.0);
+    for capacity in [1, 32].iter() {
+        let quadtree = filled_quadtree(*capacity);
+        c.bench_function(&format!("find in bounds, bucket {}", capacity), |b| {
+            b.iter(|| quadtree.find_in_bounds(black_box(&query)))
+        });
+        c.bench_function(&format!("count in bounds, bucket {}", capacity), |b| {
+            b.iter(|| quadtree.count_in_bounds(black_box(&query)))
+        });
+        c.bench_function(&format!("find in radius, bucket {}", capacity), |b| {
+            b.iter(|| quadtree.find_in_radius(black_box(center), black_box(120.0)))
+        });
+    }
+
+    // chunked scans of the buckets, against testing one position at a time, on the same tree
+    #[cfg(feature = "bench")]
+    {
+        let quadtree = filled_quadtree(32);
+        for chunked in [true, false].iter() {
+            let name = if *chunked { "chunked" } else { "scalar" };
+            c.bench_function(&format!("scan buckets in bounds, {}", name), |b| {
+                b.iter(|| quadtree.scan_in_bounds(black_box(&query), *chunked))
+            });
+        }
+    }
 }
 
 criterion_group!(benches, criterion_benchmark);
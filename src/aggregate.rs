@@ -0,0 +1,353 @@
+use crate::{
+    error::InsertError,
+    iter::Iter,
+    node::{NodeId, NodeRef},
+    point::Point,
+    quadtree::QuadTree,
+    rectangle::{Rectangle, RectangleRelation},
+    TreeNode,
+};
+
+/// A user-defined summary of data, like a count, a sum of weights or the minimum of a field,
+/// which is cached for every node of an [`AggregateQuadTree`].
+///
+/// `combine` has to be associative, and `empty` has to be it's neutral element,
+/// because data is combined in no particular order and grouping.
+///
+/// # Examples
+///
+/// ```
+/// use quadtree::{Point, Summary};
+///
+/// /// The number of sales and their total
+/// #[derive(Debug, Clone, PartialEq)]
+/// struct Sales {
+///     count: usize,
+///     total: f64,
+/// }
+///
+/// impl Summary<f64> for Sales {
+///     fn empty() -> Self {
+///         Sales { count: 0, total: 0.0 }
+///     }
+///
+///     fn from_item(_position: &Point, amount: &f64) -> Self {
+///         Sales { count: 1, total: *amount }
+///     }
+///
+///     fn combine(&self, other: &Self) -> Self {
+///         Sales {
+///             count: self.count + other.count,
+///             total: self.total + other.total,
+///         }
+///     }
+/// }
+/// ```
+pub trait Summary<T>: Clone {
+    /// The summary of no data at all
+    fn empty() -> Self;
+
+    /// The summary of a single data point
+    fn from_item(position: &Point, data: &T) -> Self;
+
+    /// The summary of the data of both summaries together
+    fn combine(&self, other: &Self) -> Self;
+}
+
+/// A [`QuadTree`] caching a [`Summary`] of the data below every node,
+/// so aggregates over a rectangle only visit the nodes crossing it's edges.
+///
+/// Only the nodes on the path to an inserted or removed position are updated,
+/// all other queries are available through [`AggregateQuadTree::tree`].
+///
+/// # Examples
+///
+/// ```
+/// use quadtree::{AggregateQuadTree, Point, Rectangle, Summary};
+///
+/// #[derive(Debug, Clone, PartialEq)]
+/// struct Total(u32);
+///
+/// impl Summary<u32> for Total {
+///     fn empty() -> Self {
+///         Total(0)
+///     }
+///
+///     fn from_item(_position: &Point, sales: &u32) -> Self {
+///         Total(*sales)
+///     }
+///
+///     fn combine(&self, other: &Self) -> Self {
+///         Total(self.0 + other.0)
+///     }
+/// }
+///
+/// let mut shops: AggregateQuadTree<u32, Total> =
+///     AggregateQuadTree::new_bounded(&Rectangle::new((0.0, 0.0), 10.0, 10.0));
+/// shops.insert(120, (1.0, 1.0));
+/// shops.insert(80, (2.0, 3.0));
+/// shops.insert(300, (8.0, 8.0));
+///
+/// let viewport = Rectangle::new((0.0, 0.0), 5.0, 5.0);
+/// assert_eq!(Total(200), shops.aggregate_in_bounds(&viewport));
+/// assert_eq!(Total(500), shops.summary());
+/// ```
+#[derive(Clone)]
+pub struct AggregateQuadTree<T, S> {
+    tree: QuadTree<T>,
+
+    /// the summary below every node, indexed by it's [`NodeId`]
+    summaries: Vec<S>,
+}
+
+impl<T, S> AggregateQuadTree<T, S>
+where
+    S: Summary<T>,
+{
+    /// Creates an empty tree with the given bounds, see [`QuadTree::new_bounded`]
+    pub fn new_bounded(bounds: &Rectangle) -> Self {
+        QuadTree::new_bounded(bounds).into()
+    }
+
+    /// Gets the tree, for all queries besides aggregates
+    pub fn tree(&self) -> &QuadTree<T> {
+        &self.tree
+    }
+
+    /// Gives back the tree, dropping the summaries
+    pub fn into_tree(self) -> QuadTree<T> {
+        self.tree
+    }
+
+    /// Inserts data like [`QuadTree::insert`], and updates the summaries
+    pub fn insert(&mut self, data: T, position: impl Into<Point>) -> bool {
+        self.try_insert(data, position).is_ok()
+    }
+
+    /// Inserts data like [`QuadTree::try_insert`], and updates the summaries
+    pub fn try_insert(&mut self, data: T, position: impl Into<Point>) -> Result<(), InsertError> {
+        let position = position.into();
+        self.tree.try_insert(data, position)?;
+        self.refresh(&position);
+        Ok(())
+    }
+
+    /// Removes data like [`QuadTree::remove`], and updates the summaries
+    pub fn remove(&mut self, pt: impl Into<Point>) -> Option<T> {
+        let pt = pt.into();
+        let removed = self.tree.remove(pt);
+        if removed.is_some() {
+            self.refresh(&pt);
+        }
+        removed
+    }
+
+    /// Gets the summary of all data in the tree
+    pub fn summary(&self) -> S {
+        self.cached(self.tree.root().id())
+    }
+
+    /// Combines the summaries of all data inside of the given bounds.
+    /// Nodes fully inside of the bounds add their cached summary,
+    /// so only data in nodes crossing the edges of the bounds is summarized one by one.
+    ///
+    /// # Arguments
+    ///
+    /// * `bounds` - The bounds inside of which data should be summarized
+    pub fn aggregate_in_bounds(&self, bounds: &Rectangle) -> S {
+        self.aggregate(self.tree.root(), bounds)
+    }
+
+    /// Gets an iterator over the positions and data of the tree, see [`QuadTree::iter`]
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.tree.iter()
+    }
+
+    /// Gets the number of data points in the tree
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Test if the tree holds no data
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    fn aggregate(&self, node: NodeRef<'_, T>, bounds: &Rectangle) -> S {
+        match node.bounds().relation(bounds) {
+            RectangleRelation::Disjoint => S::empty(),
+            RectangleRelation::Containment(false) => self.cached(node.id()),
+            _ => node
+                .node()
+                .quadrants()
+                .flatten()
+                .fold(S::empty(), |summary, tn| match tn {
+                    TreeNode::Node(id) => summary.combine(&self.aggregate(node.get(*id), bounds)),
+                    tn => tn
+                        .data()
+                        .iter()
+                        .filter(|data| bounds.contains(*data.position()))
+                        .fold(summary, |summary, data| {
+                            summary.combine(&S::from_item(data.position(), data.data()))
+                        }),
+                }),
+        }
+    }
+
+    fn cached(&self, id: NodeId) -> S {
+        self.summaries[id.0 as usize].clone()
+    }
+
+    /// Summarizes the quadrants of a node, using the cached summaries of it's child nodes
+    fn summarize(&self, node: NodeRef<'_, T>) -> S {
+        node.node()
+            .quadrants()
+            .flatten()
+            .fold(S::empty(), |summary, tn| match tn {
+                TreeNode::Node(id) => summary.combine(&self.cached(*id)),
+                tn => tn.data().iter().fold(summary, |summary, data| {
+                    summary.combine(&S::from_item(data.position(), data.data()))
+                }),
+            })
+    }
+
+    fn store(&mut self, id: NodeId, summary: S) {
+        let index = id.0 as usize;
+        if index >= self.summaries.len() {
+            self.summaries.resize(index + 1, S::empty());
+        }
+        self.summaries[index] = summary;
+    }
+
+    /// Updates the summaries of all nodes on the path to the given position, deepest first.
+    /// Inserting and removing only changes the nodes on this path.
+    fn refresh(&mut self, pt: &Point) {
+        let mut path = Vec::new();
+        let mut node = Some(self.tree.root());
+        while let Some(current) = node {
+            path.push(current.id());
+            node = current.bounds().find_quadrant(pt).and_then(|quadrant| {
+                match current.quadrant(&quadrant) {
+                    Some(TreeNode::Node(id)) => Some(current.get(*id)),
+                    _ => None,
+                }
+            });
+        }
+
+        for id in path.into_iter().rev() {
+            let summary = self.summarize(self.tree.root().get(id));
+            self.store(id, summary);
+        }
+    }
+
+    /// Updates the summaries of all nodes below the given one, deepest first
+    fn refresh_below(&mut self, id: NodeId) {
+        let children: Vec<NodeId> = self.tree.root().get(id).nodes().map(|n| n.id()).collect();
+        for child in children {
+            self.refresh_below(child);
+        }
+        let summary = self.summarize(self.tree.root().get(id));
+        self.store(id, summary);
+    }
+}
+
+impl<T, S> From<QuadTree<T>> for AggregateQuadTree<T, S>
+where
+    S: Summary<T>,
+{
+    /// Summarizes all data of the tree, so trees configured with a
+    /// [`QuadTreeBuilder`](crate::QuadTreeBuilder) can be aggregated
+    fn from(tree: QuadTree<T>) -> Self {
+        let mut aggregate = Self {
+            tree,
+            summaries: Vec::new(),
+        };
+        aggregate.refresh_below(aggregate.tree.root().id());
+        aggregate
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::builder::QuadTreeBuilder;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct CountMax {
+        count: usize,
+        max: Option<usize>,
+    }
+
+    impl Summary<usize> for CountMax {
+        fn empty() -> Self {
+            CountMax {
+                count: 0,
+                max: None,
+            }
+        }
+
+        fn from_item(_position: &Point, data: &usize) -> Self {
+            CountMax {
+                count: 1,
+                max: Some(*data),
+            }
+        }
+
+        fn combine(&self, other: &Self) -> Self {
+            CountMax {
+                count: self.count + other.count,
+                max: self.max.max(other.max),
+            }
+        }
+    }
+
+    #[test]
+    fn aggregate_matches_brute_force_after_updates() {
+        // Arrange
+        let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+        let builders = [
+            QuadTreeBuilder::new(&bounds),
+            QuadTreeBuilder::new(&bounds).bucket_capacity(4),
+            QuadTreeBuilder::new(&bounds).compressed(true),
+        ];
+        let positions: Vec<Point> = (0..300usize)
+            .map(|i| Point::new((i * 37 % 100) as f32 / 10.0, (i * 61 % 97) as f32 / 9.7))
+            .collect();
+        let queries = [
+            Rectangle::new((0.0, 0.0), 10.0, 10.0),
+            Rectangle::new((2.5, 2.5), 5.0, 5.0),
+            Rectangle::new((1.3, 6.1), 2.2, 3.7),
+            Rectangle::new((-5.0, -5.0), 7.5, 20.0),
+        ];
+
+        for builder in builders.iter() {
+            let mut aggregate: AggregateQuadTree<usize, CountMax> = builder.build().into();
+            for (i, position) in positions.iter().enumerate() {
+                assert!(aggregate.insert(i, *position));
+            }
+
+            // Act
+            for position in positions.iter().step_by(3) {
+                assert!(aggregate.remove(*position).is_some());
+            }
+
+            // Assert
+            for query in queries.iter() {
+                let expected = aggregate
+                    .iter()
+                    .filter(|(position, _)| query.contains(**position))
+                    .fold(CountMax::empty(), |summary, (position, data)| {
+                        summary.combine(&CountMax::from_item(position, data))
+                    });
+                assert_eq!(expected, aggregate.aggregate_in_bounds(query));
+            }
+            assert_eq!(
+                CountMax {
+                    count: 200,
+                    max: Some(299)
+                },
+                aggregate.summary()
+            );
+        }
+    }
+}
@@ -0,0 +1,671 @@
+use crate::{
+    bucket::Bucket,
+    node::{Node, NodeId, NodeRef},
+    point::Point,
+    rectangle::{Quadrant, Rectangle, RectangleRelation},
+    spatial::Spatial,
+    TreeNode,
+};
+
+/// Storage for all nodes of a tree.
+///
+/// Nodes are stored in a flat `Vec` and reference their children by [`NodeId`],
+/// so splitting a quadrant doesn't need a heap allocation of it's own,
+/// and dropping a tree never recurses.
+/// Released nodes are kept in a free list and reused by the next split.
+#[derive(Clone)]
+pub(crate) struct Arena<T>
+where
+    T: Sized,
+{
+    /// The root node lives inline, so creating an empty arena doesn't allocate
+    root: Node<T>,
+
+    /// All other nodes, the node with id `i` is stored at index `i - 1`
+    nodes: Vec<Node<T>>,
+
+    /// Ids of released nodes, which can be reused
+    free: Vec<NodeId>,
+
+    /// The number of data points a leaf holds before it is split, leafs are stored as buckets if > 1
+    bucket_capacity: usize,
+}
+
+impl<T> Arena<T>
+where
+    T: Sized,
+{
+    pub(crate) const fn new_bounded(bounds: &Rectangle) -> Self {
+        Self {
+            root: Node::new_bounded(bounds),
+            nodes: Vec::new(),
+            free: Vec::new(),
+            bucket_capacity: 1,
+        }
+    }
+
+    /// Set the number of data points a leaf holds before it is split, has to be set before inserting
+    pub(crate) fn set_bucket_capacity(&mut self, capacity: usize) {
+        debug_assert!(
+            self.root.is_empty(),
+            "Bucket capacity can only be set on an empty arena"
+        );
+        self.bucket_capacity = capacity.max(1);
+    }
+
+    pub(crate) fn bucket_capacity(&self) -> usize {
+        self.bucket_capacity
+    }
+
+    /// Create a leaf holding a single data point
+    fn leaf(&self, data: Spatial<T>) -> TreeNode<T> {
+        if self.bucket_capacity > 1 {
+            TreeNode::Bucket(Bucket::new(vec![data]))
+        } else {
+            TreeNode::Point(data)
+        }
+    }
+
+    /// Gets a read-only reference to the root node
+    pub(crate) fn root(&self) -> NodeRef<'_, T> {
+        NodeRef::new(self, NodeId::ROOT)
+    }
+
+    /// Gets a reference to the node with the given id
+    pub(crate) fn get(&self, id: NodeId) -> &Node<T> {
+        match id {
+            NodeId::ROOT => &self.root,
+            NodeId(index) => &self.nodes[index as usize - 1],
+        }
+    }
+
+    /// Gets a mutable reference to the node with the given id
+    pub(crate) fn get_mut(&mut self, id: NodeId) -> &mut Node<T> {
+        match id {
+            NodeId::ROOT => &mut self.root,
+            NodeId(index) => &mut self.nodes[index as usize - 1],
+        }
+    }
+
+    /// Returns the number of nodes in use, including the root
+    pub(crate) fn len(&self) -> usize {
+        1 + self.nodes.len() - self.free.len()
+    }
+
+    /// Returns the number of bytes allocated on the heap for node storage
+    pub(crate) fn heap_bytes(&self) -> usize {
+        self.nodes.capacity() * std::mem::size_of::<Node<T>>()
+            + self.free.capacity() * std::mem::size_of::<NodeId>()
+    }
+
+    /// Remove all nodes and data, but keep the allocated memory for reuse
+    pub(crate) fn clear(&mut self) {
+        self.root = Node::new_bounded(self.root.bounds());
+        self.nodes.clear();
+        self.free.clear();
+    }
+
+    /// Store a new, empty node with the given bounds, reusing a released slot if possible
+    fn alloc(&mut self, bounds: &Rectangle) -> NodeId {
+        match self.free.pop() {
+            Some(id) => {
+                *self.get_mut(id) = Node::new_bounded(bounds);
+                id
+            }
+            None => {
+                self.nodes.push(Node::new_bounded(bounds));
+                NodeId(self.nodes.len() as u32)
+            }
+        }
+    }
+
+    /// Mark the node with the given id as unused, it has to be unlinked from it's parent already
+    fn release(&mut self, id: NodeId) {
+        debug_assert!(id != NodeId::ROOT, "The root node can not be released");
+        self.free.push(id);
+    }
+
+    /// Split the given quadrant of the given node.
+    /// The only case in which this actually does something,
+    /// is if the given quadrant contains a single data point or a bucket.
+    /// The data will be replaced with a new node in which the data is inserted.
+    /// Returns the number of newly created nodes.
+    fn split(&mut self, id: NodeId, quadrant: &Quadrant) -> usize {
+        match self.get_mut(id).quadrant_mut(quadrant).take() {
+            // we can't split none :/
+            None => 0,
+            // If we took out spatial data, we create a new node and insert the data in it
+            // After that we replace the None in quadrant with the new node
+            Some(TreeNode::Point(data)) => {
+                let bounds = self.get(id).bounds().create_quadrant(quadrant);
+                let child = self.alloc(&bounds);
+                self.insert_below(child, data);
+                self.get_mut(id)
+                    .quadrant_mut(quadrant)
+                    .replace(TreeNode::Node(child));
+                1
+            }
+            Some(TreeNode::Bucket(bucket)) => {
+                let bounds = self.get(id).bounds().create_quadrant(quadrant);
+                let child = self.alloc(&bounds);
+                let created = bucket
+                    .into_items()
+                    .into_iter()
+                    .map(|data| self.insert_below(child, data))
+                    .sum::<usize>();
+                self.get_mut(id)
+                    .quadrant_mut(quadrant)
+                    .replace(TreeNode::Node(child));
+                created + 1
+            }
+            // If we took out a node, we just put it back in :)
+            Some(tn) => {
+                self.get_mut(id).quadrant_mut(quadrant).replace(tn);
+                0
+            }
+        }
+    }
+
+    /// Insert a [`Spatial`] into the tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - A [`Spatial`] holding data linked to a position in space
+    ///
+    /// # Panics
+    ///
+    /// This function will loop forever if two Spatial at the exact same position are inserted.
+    ///
+    /// Returns the number of nodes that had to be created to make room for the data.
+    ///
+    pub(crate) fn insert(&mut self, data: Spatial<T>) -> usize {
+        self.insert_below(NodeId::ROOT, data)
+    }
+
+    fn insert_below(&mut self, mut id: NodeId, data: Spatial<T>) -> usize {
+        let mut created = 0;
+        let capacity = self.bucket_capacity;
+        loop {
+            let node = self.get(id);
+            // get Quadrant of data
+            let quadrant = node
+                .bounds()
+                .find_quadrant(data.position())
+                .expect("data outside of bounds!");
+
+            match node.quadrant(&quadrant) {
+                // if the quadrant is still empty, we can insert the data and exit
+                None => {
+                    let leaf = self.leaf(data);
+                    let node = self.get_mut(id);
+                    node.quadrant_mut(&quadrant).replace(leaf);
+                    *node.count_mut() += 1;
+                    return created;
+                }
+                Some(TreeNode::Node(child)) => {
+                    let child = *child;
+                    *self.get_mut(id).count_mut() += 1;
+                    id = child;
+                }
+                // a bucket with room left takes the data
+                Some(TreeNode::Bucket(bucket)) if bucket.len() < capacity => {
+                    let node = self.get_mut(id);
+                    if let Some(TreeNode::Bucket(bucket)) = node.quadrant_mut(&quadrant) {
+                        bucket.push(data);
+                    }
+                    *node.count_mut() += 1;
+                    return created;
+                }
+                // we need to split, and try again
+                Some(_) => created += self.split(id, &quadrant),
+            }
+        }
+    }
+
+    /// Insert a [`Spatial`] into a path-compressed tree.
+    ///
+    /// Instead of splitting a quadrant level by level until two data points are separated,
+    /// a single node is created with the smallest cell containing both,
+    /// so every insertion creates at most one node.
+    /// Child nodes don't necessarily cover the whole quadrant they are stored in.
+    ///
+    /// # Panics
+    ///
+    /// This function will loop forever if two Spatial at the exact same position are inserted.
+    ///
+    /// Returns the number of nodes that had to be created to make room for the data.
+    ///
+    pub(crate) fn insert_compressed(&mut self, data: Spatial<T>) -> usize {
+        let mut id = NodeId::ROOT;
+        loop {
+            let node = self.get(id);
+            let quadrant = node
+                .bounds()
+                .find_quadrant(data.position())
+                .expect("data outside of bounds!");
+
+            // the positions, which separate whatever is stored in the quadrant from the new data
+            let mut occupants = match node.quadrant(&quadrant) {
+                None => {
+                    let leaf = self.leaf(data);
+                    let node = self.get_mut(id);
+                    node.quadrant_mut(&quadrant).replace(leaf);
+                    *node.count_mut() += 1;
+                    return 0;
+                }
+                Some(TreeNode::Point(existing)) => vec![*existing.position()],
+                Some(TreeNode::Bucket(bucket)) if bucket.len() < self.bucket_capacity => {
+                    let node = self.get_mut(id);
+                    if let Some(TreeNode::Bucket(bucket)) = node.quadrant_mut(&quadrant) {
+                        bucket.push(data);
+                    }
+                    *node.count_mut() += 1;
+                    return 0;
+                }
+                Some(TreeNode::Bucket(bucket)) => bucket
+                    .items()
+                    .iter()
+                    .map(|existing| *existing.position())
+                    .collect(),
+                Some(TreeNode::Node(child)) => {
+                    let child_bounds = self.get(*child).bounds();
+                    if child_bounds.find_quadrant(data.position()).is_some() {
+                        let child = *child;
+                        *self.get_mut(id).count_mut() += 1;
+                        id = child;
+                        continue;
+                    }
+                    vec![child_bounds.center()]
+                }
+            };
+
+            // put a new node with the smallest cell containing all of them in between
+            occupants.push(*data.position());
+            let bounds = node
+                .bounds()
+                .create_quadrant(&quadrant)
+                .common_cell(&occupants);
+            let existing = self
+                .get_mut(id)
+                .quadrant_mut(&quadrant)
+                .take()
+                .expect("Quadrant can not be empty here");
+            let between = self.alloc(&bounds);
+
+            // the cell separates all data, so no quadrant of the new node has to be split
+            match existing {
+                TreeNode::Point(existing) => {
+                    self.insert_below(between, existing);
+                }
+                TreeNode::Bucket(bucket) => {
+                    for existing in bucket.into_items() {
+                        self.insert_below(between, existing);
+                    }
+                }
+                node => {
+                    let existing_quadrant = bounds
+                        .find_quadrant(&occupants[0])
+                        .expect("Cell has to contain the existing data");
+                    let count = match &node {
+                        TreeNode::Node(child) => self.get(*child).count(),
+                        _ => unreachable!("Points and buckets were handled above"),
+                    };
+                    let between = self.get_mut(between);
+                    between.quadrant_mut(&existing_quadrant).replace(node);
+                    *between.count_mut() = count;
+                }
+            }
+            self.insert_below(between, data);
+
+            let node = self.get_mut(id);
+            node.quadrant_mut(&quadrant)
+                .replace(TreeNode::Node(between));
+            *node.count_mut() += 1;
+            return 1;
+        }
+    }
+
+    /// Gets mutable access to the data stored exactly at the given position, if any
+    pub(crate) fn find_mut(&mut self, pt: &Point) -> Option<&mut T> {
+        let mut id = NodeId::ROOT;
+        let quadrant = loop {
+            let node = self.get(id);
+            let quadrant = node.bounds().find_quadrant(pt)?;
+            match node.quadrant(&quadrant) {
+                Some(TreeNode::Node(child)) => id = *child,
+                _ => break quadrant,
+            }
+        };
+
+        match self.get_mut(id).quadrant_mut(&quadrant) {
+            Some(TreeNode::Point(data)) if data.position() == pt => Some(data.parts_mut().1),
+            Some(TreeNode::Bucket(bucket)) => bucket.find_mut(pt).map(|data| data.parts_mut().1),
+            _ => None,
+        }
+    }
+
+    /// Tries to remove the data at the given position,
+    /// And returns ownership to it.
+    /// If no data is stored at the given position, the return value will be `None`.
+    /// Nodes left empty are only removed by [`Arena::collapse`].
+    pub(crate) fn remove(&mut self, pt: &Point) -> Option<T> {
+        let mut path = Vec::new();
+        let mut id = NodeId::ROOT;
+        let removed = loop {
+            path.push(id);
+            let node = self.get_mut(id);
+            let quadrant = node.bounds().find_quadrant(pt)?;
+
+            match node.quadrant(&quadrant) {
+                Some(TreeNode::Node(child)) => id = *child,
+                Some(TreeNode::Point(data)) if data.position() == pt => {
+                    break match node.quadrant_mut(&quadrant).take() {
+                        Some(TreeNode::Point(data)) => data.consume(),
+                        _ => unreachable!("Quadrant was checked to hold a data point"),
+                    };
+                }
+                Some(TreeNode::Bucket(_)) => {
+                    let slot = node.quadrant_mut(&quadrant);
+                    let removed = match slot {
+                        Some(TreeNode::Bucket(bucket)) => bucket.remove(pt),
+                        _ => unreachable!("Quadrant was checked to hold a bucket"),
+                    };
+                    if matches!(slot, Some(TreeNode::Bucket(bucket)) if bucket.is_empty()) {
+                        slot.take();
+                    }
+                    break removed?.consume();
+                }
+                _ => return None,
+            }
+        };
+
+        for id in path {
+            *self.get_mut(id).count_mut() -= 1;
+        }
+        Some(removed)
+    }
+
+    /// Subdivide the leaf containing the given point, so it's quadrant holds a new child node.
+    /// Empty quadrants get a new, empty node, data points are split.
+    /// Returns the number of newly created nodes.
+    pub(crate) fn subdivide_leaf_at(&mut self, pt: &Point) -> usize {
+        let mut id = NodeId::ROOT;
+        loop {
+            let node = self.get(id);
+            let quadrant = match node.bounds().find_quadrant(pt) {
+                None => return 0,
+                Some(quadrant) => quadrant,
+            };
+
+            match node.quadrant(&quadrant) {
+                Some(TreeNode::Node(child)) => id = *child,
+                Some(TreeNode::Point(_)) | Some(TreeNode::Bucket(_)) => {
+                    return self.split(id, &quadrant)
+                }
+                None => {
+                    let bounds = node.bounds().create_quadrant(&quadrant);
+                    let child = self.alloc(&bounds);
+                    self.get_mut(id)
+                        .quadrant_mut(&quadrant)
+                        .replace(TreeNode::Node(child));
+                    return 1;
+                }
+            }
+        }
+    }
+
+    /// Collapse the whole tree, returning all data points stored.
+    /// All nodes except the root are released for reuse.
+    pub(crate) fn collapse(&mut self) -> Vec<Spatial<T>> {
+        self.collapse_below(NodeId::ROOT)
+    }
+
+    /// Collapse the subtree of the given node, returning all data points stored in it.
+    /// All nodes of the subtree except the root are released for reuse,
+    /// so any other node has to be unlinked from it's parent already.
+    fn collapse_below(&mut self, id: NodeId) -> Vec<Spatial<T>> {
+        let mut data: Vec<Spatial<T>> = Vec::new();
+        let mut stack = vec![id];
+
+        while let Some(id) = stack.pop() {
+            self.get_mut(id)
+                .quadrants_mut()
+                .for_each(|q| match q.take() {
+                    None => (),
+                    Some(TreeNode::Point(pt)) => data.push(pt),
+                    Some(TreeNode::Bucket(bucket)) => data.extend(bucket.into_items()),
+                    Some(TreeNode::Node(child)) => stack.push(child),
+                });
+
+            if id != NodeId::ROOT {
+                self.release(id);
+            }
+        }
+
+        *self.get_mut(id).count_mut() = 0;
+        data
+    }
+
+    /// Remove all data points inside of `bounds` for which `remove` returns `true`,
+    /// restructuring the tree in the same pass.
+    /// Without bounds, every data point is tested.
+    ///
+    /// Nodes left empty are released, and nodes left with no more data points than fit into a leaf
+    /// are merged into one. In a path-compressed tree,
+    /// nodes left with a single child node are replaced by that child.
+    /// Subtrees outside of `bounds` are not visited, and therefore never merged.
+    ///
+    /// Returns the removed data points.
+    pub(crate) fn extract<F>(
+        &mut self,
+        bounds: Option<&Rectangle>,
+        compressed: bool,
+        mut remove: F,
+    ) -> Vec<Spatial<T>>
+    where
+        F: FnMut(&mut Spatial<T>) -> bool,
+    {
+        let mut removed = Vec::new();
+        self.extract_below(NodeId::ROOT, bounds, compressed, &mut remove, &mut removed);
+        removed
+    }
+
+    /// Returns the number of data points left below the given node,
+    /// or anything above the bucket capacity if that is unknown.
+    fn extract_below<F>(
+        &mut self,
+        id: NodeId,
+        bounds: Option<&Rectangle>,
+        compressed: bool,
+        remove: &mut F,
+        removed: &mut Vec<Spatial<T>>,
+    ) -> usize
+    where
+        F: FnMut(&mut Spatial<T>) -> bool,
+    {
+        let capacity = self.bucket_capacity;
+        let in_bounds = |data: &Spatial<T>| match bounds {
+            None => true,
+            Some(bounds) => bounds.find_quadrant(data.position()).is_some(),
+        };
+
+        let mut left = 0;
+        for quadrant in Quadrant::ALL.iter() {
+            let kept = match self.get_mut(id).quadrant_mut(quadrant).take() {
+                None => None,
+                Some(TreeNode::Point(mut data)) => {
+                    if in_bounds(&data) && remove(&mut data) {
+                        removed.push(data);
+                        None
+                    } else {
+                        left += 1;
+                        Some(TreeNode::Point(data))
+                    }
+                }
+                Some(TreeNode::Bucket(bucket)) => {
+                    let mut items = Vec::with_capacity(bucket.len());
+                    for mut data in bucket.into_items() {
+                        if in_bounds(&data) && remove(&mut data) {
+                            removed.push(data);
+                        } else {
+                            items.push(data);
+                        }
+                    }
+                    left += items.len();
+                    if items.is_empty() {
+                        None
+                    } else {
+                        Some(TreeNode::Bucket(Bucket::new(items)))
+                    }
+                }
+                Some(TreeNode::Node(child)) => {
+                    let visit = match bounds {
+                        None => true,
+                        Some(bounds) => {
+                            self.get(child).bounds().relation(bounds) != RectangleRelation::Disjoint
+                        }
+                    };
+                    let child_left = if visit {
+                        self.extract_below(child, bounds, compressed, remove, removed)
+                    } else {
+                        capacity + 1
+                    };
+                    left += child_left;
+                    self.merge(child, child_left, compressed)
+                }
+            };
+            *self.get_mut(id).quadrant_mut(quadrant) = kept;
+        }
+
+        self.recount(id);
+        left
+    }
+
+    /// Recompute the cached count of the given node from it's quadrants and the counts of it's child nodes
+    fn recount(&mut self, id: NodeId) {
+        let count = self
+            .get(id)
+            .quadrants()
+            .flatten()
+            .map(|tn| match tn {
+                TreeNode::Node(child) => self.get(*child).count(),
+                tn => tn.data().len(),
+            })
+            .sum();
+        *self.get_mut(id).count_mut() = count;
+    }
+
+    /// Shrink the given child node after data points were removed below it,
+    /// returning what should be stored in it's place.
+    fn merge(&mut self, child: NodeId, left: usize, compressed: bool) -> Option<TreeNode<T>> {
+        if left == 0 {
+            self.collapse_below(child);
+            return None;
+        }
+
+        if left <= self.bucket_capacity {
+            let mut data = self.collapse_below(child);
+            return Some(if self.bucket_capacity > 1 {
+                TreeNode::Bucket(Bucket::new(data))
+            } else {
+                TreeNode::Point(data.pop().expect("Subtree holds a data point"))
+            });
+        }
+
+        if compressed {
+            let grandchild = {
+                let mut children = self.get(child).quadrants().flatten();
+                match (children.next(), children.next()) {
+                    (Some(TreeNode::Node(grandchild)), None) => Some(*grandchild),
+                    _ => None,
+                }
+            };
+            if let Some(grandchild) = grandchild {
+                self.release(child);
+                return Some(TreeNode::Node(grandchild));
+            }
+        }
+
+        Some(TreeNode::Node(child))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn arena_reuses_released_nodes() {
+        // Arrange
+        let mut arena: Arena<u8> = Arena::new_bounded(&Rectangle::new((0.0, 0.0), 8.0, 8.0));
+        assert_eq!(0, arena.insert((0, Point::new(1.0, 1.0)).into()));
+        assert_eq!(2, arena.insert((1, Point::new(1.0, 1.5)).into()));
+        assert_eq!(3, arena.len());
+
+        // Act
+        let data = arena.collapse();
+
+        // Assert
+        assert_eq!(2, data.len());
+        assert_eq!(1, arena.len());
+        assert!(arena.root().is_empty());
+
+        // reinserting takes the released slots instead of growing the storage
+        for d in data {
+            arena.insert(d);
+        }
+        assert_eq!(3, arena.len());
+        assert_eq!(2, arena.nodes.len());
+        assert!(arena.free.is_empty());
+    }
+
+    #[test]
+    fn arena_compressed_insert_skips_chains() {
+        // Arrange
+        let mut arena: Arena<u8> = Arena::new_bounded(&Rectangle::new((0.0, 0.0), 8.0, 8.0));
+
+        // Act
+        assert_eq!(0, arena.insert_compressed((0, Point::new(1.1, 1.1)).into()));
+        assert_eq!(
+            1,
+            arena.insert_compressed((1, Point::new(1.101, 1.1)).into())
+        );
+        assert_eq!(1, arena.insert_compressed((2, Point::new(1.1, 1.6)).into()));
+        assert_eq!(0, arena.insert_compressed((3, Point::new(7.0, 7.0)).into()));
+
+        // Assert
+        assert_eq!(3, arena.len());
+        let root = arena.root();
+        let between = root.child(&Quadrant::BottomLeft).unwrap();
+        assert_eq!(&Rectangle::new((1.0, 1.0), 1.0, 1.0), between.bounds());
+        assert!(
+            between
+                .child(&Quadrant::BottomLeft)
+                .unwrap()
+                .bounds()
+                .width()
+                < 0.01
+        );
+        assert_eq!(Some(&1), root.find(&Point::new(1.101, 1.1)));
+        assert_eq!(Some(&2), root.find(&Point::new(1.1, 1.6)));
+        assert_eq!(None, root.find(&Point::new(1.5, 1.1)));
+    }
+
+    #[test]
+    fn arena_clear_keeps_capacity() {
+        // Arrange
+        let mut arena: Arena<u8> = Arena::new_bounded(&Rectangle::new((0.0, 0.0), 8.0, 8.0));
+        arena.insert((0, Point::new(1.0, 1.0)).into());
+        arena.insert((1, Point::new(1.0, 1.5)).into());
+        let heap_bytes = arena.heap_bytes();
+
+        // Act
+        arena.clear();
+
+        // Assert
+        assert_eq!(1, arena.len());
+        assert!(arena.root().is_empty());
+        assert_eq!(&Rectangle::new((0.0, 0.0), 8.0, 8.0), arena.root().bounds());
+        assert_eq!(heap_bytes, arena.heap_bytes());
+    }
+}
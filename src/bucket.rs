@@ -0,0 +1,264 @@
+use crate::{
+    point::Point,
+    rectangle::{Boundary, Rectangle},
+    spatial::Spatial,
+};
+use std::fmt::{self, Debug};
+
+/// The number of positions tested together in the chunked scans,
+/// chosen so a chunk fills a 256 bit vector register with `f32` lanes.
+const LANES: usize = 8;
+
+/// A leaf holding up to a fixed number of data points, used for trees with a bucket capacity > 1.
+///
+/// The coordinates of all data points are additionally stored as a structure of arrays,
+/// so range and radius filters can scan them in fixed size chunks,
+/// which the compiler can turn into vector instructions.
+#[derive(Clone)]
+pub(crate) struct Bucket<T> {
+    xs: Vec<f32>,
+    ys: Vec<f32>,
+    items: Vec<Spatial<T>>,
+}
+
+impl<T> Bucket<T> {
+    /// Create a bucket from the given data points
+    pub(crate) fn new(items: Vec<Spatial<T>>) -> Self {
+        Self {
+            xs: items.iter().map(|item| item.position().x()).collect(),
+            ys: items.iter().map(|item| item.position().y()).collect(),
+            items,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Gets all data points stored in the bucket
+    pub(crate) fn items(&self) -> &[Spatial<T>] {
+        &self.items
+    }
+
+    /// Consume the bucket, returning all data points stored
+    pub(crate) fn into_items(self) -> Vec<Spatial<T>> {
+        self.items
+    }
+
+    pub(crate) fn push(&mut self, item: Spatial<T>) {
+        self.xs.push(item.position().x());
+        self.ys.push(item.position().y());
+        self.items.push(item);
+    }
+
+    fn index_of(&self, pt: &Point) -> Option<usize> {
+        self.xs
+            .iter()
+            .zip(self.ys.iter())
+            .position(|(x, y)| *x == pt.x() && *y == pt.y())
+    }
+
+    /// Finds the data point stored exactly at the given position
+    pub(crate) fn find(&self, pt: &Point) -> Option<&Spatial<T>> {
+        self.index_of(pt).map(|index| &self.items[index])
+    }
+
+    /// Finds the data point stored exactly at the given position, allowing to modify it's data
+    pub(crate) fn find_mut(&mut self, pt: &Point) -> Option<&mut Spatial<T>> {
+        self.index_of(pt).map(move |index| &mut self.items[index])
+    }
+
+    /// Removes the data point stored exactly at the given position, the order of the bucket is not kept
+    pub(crate) fn remove(&mut self, pt: &Point) -> Option<Spatial<T>> {
+        let index = self.index_of(pt)?;
+        self.xs.swap_remove(index);
+        self.ys.swap_remove(index);
+        Some(self.items.swap_remove(index))
+    }
+
+    /// Push all data points inside of the given bounds onto `found`
+    pub(crate) fn filter_in_bounds<'a>(
+        &'a self,
+        bounds: &Rectangle,
+        found: &mut Vec<&'a Spatial<T>>,
+    ) {
+        let (min_x, max_x) = (bounds.min_x(), bounds.max_x());
+        let (min_y, max_y) = (bounds.min_y(), bounds.max_y());
+        match bounds.boundary() {
+            Boundary::Closed => self.filter(
+                |x, y| (x >= min_x) & (x <= max_x) & (y >= min_y) & (y <= max_y),
+                found,
+            ),
+            Boundary::HalfOpen => self.filter(
+                |x, y| (x >= min_x) & (x < max_x) & (y >= min_y) & (y < max_y),
+                found,
+            ),
+        }
+    }
+
+    /// Push all data points inside of the given bounds onto `found`, testing one position at a time.
+    /// This is the baseline the chunked [`Bucket::filter_in_bounds`] is benchmarked against.
+    pub(crate) fn filter_in_bounds_scalar<'a>(
+        &'a self,
+        bounds: &Rectangle,
+        found: &mut Vec<&'a Spatial<T>>,
+    ) {
+        found.extend(
+            self.items
+                .iter()
+                .filter(|item| bounds.contains(*item.position())),
+        )
+    }
+
+    /// Push all data points inside of the given circle onto `found`
+    pub(crate) fn filter_in_radius<'a>(
+        &'a self,
+        center: &Point,
+        radius: f32,
+        found: &mut Vec<&'a Spatial<T>>,
+    ) {
+        let (cx, cy) = (center.x(), center.y());
+        let squared_radius = radius * radius;
+        self.filter(
+            |x, y| (x - cx) * (x - cx) + (y - cy) * (y - cy) <= squared_radius,
+            found,
+        )
+    }
+
+    /// Test all positions in chunks of [`LANES`], first computing a mask for the whole chunk
+    /// without branching, then collecting the matches.
+    fn filter<'a, F>(&'a self, test: F, found: &mut Vec<&'a Spatial<T>>)
+    where
+        F: Fn(f32, f32) -> bool,
+    {
+        let mut mask = [false; LANES];
+        let chunks = self.xs.chunks_exact(LANES).zip(self.ys.chunks_exact(LANES));
+        for (chunk, (xs, ys)) in chunks.enumerate() {
+            for lane in 0..LANES {
+                mask[lane] = test(xs[lane], ys[lane]);
+            }
+            for (lane, hit) in mask.iter().enumerate() {
+                if *hit {
+                    found.push(&self.items[chunk * LANES + lane]);
+                }
+            }
+        }
+
+        let rest = self.xs.len() - self.xs.len() % LANES;
+        for index in rest..self.xs.len() {
+            if test(self.xs[index], self.ys[index]) {
+                found.push(&self.items[index]);
+            }
+        }
+    }
+}
+
+impl<T> Debug for Bucket<T>
+where
+    T: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.items.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn bucket(n: usize) -> Bucket<usize> {
+        Bucket::new(
+            (0..n)
+                .map(|i| (i, Point::new(i as f32, (i % 5) as f32)).into())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn bucket_filters_match_scalar_tests() {
+        // Arrange
+        let bucket = bucket(21);
+        let bounds = Rectangle::new((2.5, 1.0), 12.0, 2.0);
+        let half_open = bounds.with_boundary(Boundary::HalfOpen);
+        let center = Point::new(10.0, 2.0);
+
+        // Act
+        let mut in_bounds = Vec::new();
+        bucket.filter_in_bounds(&bounds, &mut in_bounds);
+        let mut in_bounds_scalar = Vec::new();
+        bucket.filter_in_bounds_scalar(&bounds, &mut in_bounds_scalar);
+        let mut in_half_open = Vec::new();
+        bucket.filter_in_bounds(&half_open, &mut in_half_open);
+        let mut in_radius = Vec::new();
+        bucket.filter_in_radius(&center, 3.0, &mut in_radius);
+
+        // Assert
+        let expected_in_bounds: Vec<usize> = bucket
+            .items()
+            .iter()
+            .filter(|item| bounds.find_quadrant(item.position()).is_some())
+            .map(|item| *item.data())
+            .collect();
+        let expected_in_radius: Vec<usize> = bucket
+            .items()
+            .iter()
+            .filter(|item| item.position().squared_distance(&center) <= 9.0)
+            .map(|item| *item.data())
+            .collect();
+        assert_eq!(
+            expected_in_bounds,
+            in_bounds
+                .iter()
+                .map(|item| *item.data())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            expected_in_bounds,
+            in_bounds_scalar
+                .iter()
+                .map(|item| *item.data())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            expected_in_bounds
+                .iter()
+                .filter(|i| (**i % 5) != 3)
+                .copied()
+                .collect::<Vec<_>>(),
+            in_half_open
+                .iter()
+                .map(|item| *item.data())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            expected_in_radius,
+            in_radius
+                .iter()
+                .map(|item| *item.data())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn bucket_can_remove() {
+        // Arrange
+        let mut bucket = bucket(10);
+
+        // Act
+        let removed = bucket.remove(&Point::new(3.0, 3.0));
+
+        // Assert
+        assert_eq!(Some(3), removed.map(|item| item.consume()));
+        assert_eq!(9, bucket.len());
+        assert!(bucket.find(&Point::new(3.0, 3.0)).is_none());
+        assert_eq!(
+            Some(&9),
+            bucket.find(&Point::new(9.0, 4.0)).map(|item| item.data())
+        );
+        assert!(bucket.remove(&Point::new(3.0, 3.0)).is_none());
+    }
+}
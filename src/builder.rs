@@ -0,0 +1,178 @@
+use crate::{point::Point, quadtree::QuadTree, rectangle::Rectangle};
+
+/// A builder to configure a [`QuadTree`] before creating it
+///
+/// # Examples
+///
+/// ```
+/// use quadtree::{QuadTree, QuadTreeBuilder, Rectangle};
+/// let bounds = Rectangle::new((0.0, 0.0), 8.0, 8.0);
+/// let quadtree: QuadTree<u8> = QuadTreeBuilder::new(&bounds).compressed(true).build();
+/// assert!(quadtree.is_compressed());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuadTreeBuilder {
+    pub(crate) bounds: Rectangle,
+    pub(crate) compressed: bool,
+    pub(crate) bucket_capacity: usize,
+    pub(crate) tolerance: f32,
+    pub(crate) wrapping: bool,
+}
+
+impl QuadTreeBuilder {
+    /// Creates a builder for a tree with the given bounds and the default configuration
+    ///
+    /// # Arguments
+    ///
+    /// * `bounds` - A [`Rectangle`] by which the quadtree will be bounded
+    pub const fn new(bounds: &Rectangle) -> Self {
+        Self {
+            bounds: *bounds,
+            compressed: false,
+            bucket_capacity: 1,
+            tolerance: 0.0,
+            wrapping: false,
+        }
+    }
+
+    /// Store the tree path-compressed, `false` by default.
+    ///
+    /// A plain quadtree splits a quadrant level by level, until two close data points end up in
+    /// different quadrants, leaving a chain of nodes with a single child each.
+    /// A compressed tree replaces such a chain with a single node,
+    /// bounded by the smallest cell containing both data points.
+    /// The number of nodes is then linear in the number of data points,
+    /// independent of how close they are.
+    ///
+    /// Leaf based operations like [`QuadTree::neighbors`], [`QuadTree::balance`] and [`QuadTree::mesh`]
+    /// count every stored node as one level of subdivision, so they are meant for uncompressed trees.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, QuadTreeBuilder, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 8.0, 8.0);
+    /// let mut plain: QuadTree<u8> = QuadTreeBuilder::new(&bounds).build();
+    /// let mut compressed: QuadTree<u8> = QuadTreeBuilder::new(&bounds).compressed(true).build();
+    ///
+    /// for quadtree in [&mut plain, &mut compressed].iter_mut() {
+    ///     quadtree.insert(1, (1.1, 1.1));
+    ///     quadtree.insert(2, (1.1001, 1.1));
+    /// }
+    ///
+    /// assert!(plain.node_count() > 10);
+    /// assert_eq!(2, compressed.node_count());
+    /// assert_eq!(Some(&2), compressed.find_exact((1.1001, 1.1)));
+    /// ```
+    pub fn compressed(mut self, compressed: bool) -> Self {
+        self.compressed = compressed;
+        self
+    }
+
+    /// The number of data points a leaf holds before it is split, `1` by default.
+    ///
+    /// Leaves holding more than one data point store their positions as separate arrays
+    /// of x and y coordinates, which range and radius queries scan in fixed size chunks
+    /// instead of descending further for every single data point.
+    /// This trades a few more distance tests for a much flatter tree.
+    /// A capacity of `0` is treated as `1`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, QuadTreeBuilder, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 8.0, 8.0);
+    /// let mut quadtree: QuadTree<usize> = QuadTreeBuilder::new(&bounds).bucket_capacity(16).build();
+    ///
+    /// for i in 0..16 {
+    ///     quadtree.insert(i, (i as f32 * 0.1, 1.0));
+    /// }
+    ///
+    /// assert_eq!(16, quadtree.bucket_capacity());
+    /// assert_eq!(1, quadtree.node_count());
+    /// assert_eq!(5, quadtree.find_in_radius((0.7, 1.0), 0.25).unwrap().len());
+    /// ```
+    pub fn bucket_capacity(mut self, capacity: usize) -> Self {
+        self.bucket_capacity = capacity.max(1);
+        self
+    }
+
+    /// The distance within which positions count as duplicates on insertion, `0.0` by default.
+    ///
+    /// [`QuadTree::insert`] rejects data closer than this to data already in the tree,
+    /// and [`QuadTree::insert_or_merge`] merges it into the closest data instead.
+    /// Negative values are treated as `0.0`, which only rejects positions that are exactly equal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, QuadTreeBuilder, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 8.0, 8.0);
+    /// let mut quadtree: QuadTree<u8> = QuadTreeBuilder::new(&bounds).tolerance(0.01).build();
+    ///
+    /// assert!(quadtree.insert(1, (2.0, 7.0)));
+    /// assert!(!quadtree.insert(2, (2.001, 6.999)));
+    /// assert!(quadtree.insert(3, (2.1, 7.0)));
+    /// assert_eq!(2, quadtree.len());
+    /// ```
+    pub fn tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance.max(0.0);
+        self
+    }
+
+    /// Let the space of the tree wrap around at the edges of it's bounds like a torus, `false` by default.
+    ///
+    /// Query rectangles crossing an edge continue at the opposite edge,
+    /// and radius and nearest neighbor queries measure the shortest distance around the edges.
+    /// Data can still only be inserted inside of the bounds. Their right and top edge are left out,
+    /// as they are the left and bottom edge again, see [`Boundary::HalfOpen`](crate::Boundary::HalfOpen).
+    /// Duplicates and approximate lookups are found around the edges as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, QuadTreeBuilder, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+    /// let mut quadtree: QuadTree<u8> = QuadTreeBuilder::new(&bounds).wrapping(true).build();
+    ///
+    /// quadtree.insert(1, (0.5, 5.0));
+    /// quadtree.insert(2, (6.0, 5.0));
+    ///
+    /// assert_eq!(Some(&1), quadtree.find_nearest_neighbor((9.5, 5.0)));
+    /// assert_eq!(Some(vec![&1]), quadtree.find_in_bounds(&Rectangle::new((9.0, 4.0), 2.0, 2.0)));
+    /// ```
+    pub fn wrapping(mut self, wrapping: bool) -> Self {
+        self.wrapping = wrapping;
+        self
+    }
+
+    /// Create an empty [`QuadTree`] with the configuration of this builder
+    pub fn build<T>(&self) -> QuadTree<T> {
+        QuadTree::from_builder(self)
+    }
+
+    /// Create a [`QuadTree`] with the configuration of this builder, holding all given data.
+    /// The bounds grow if any data lies outside of them, see [`QuadTree::extend`](Extend::extend).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, QuadTreeBuilder, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 8.0, 8.0);
+    /// let quadtree: QuadTree<u8> = QuadTreeBuilder::new(&bounds)
+    ///     .compressed(true)
+    ///     .build_from(vec![((1.0, 1.0), 3), ((4.0, 2.0), 2)]);
+    ///
+    /// assert!(quadtree.is_compressed());
+    /// assert_eq!(2, quadtree.len());
+    /// ```
+    pub fn build_from<T, P, I>(&self, data: I) -> QuadTree<T>
+    where
+        P: Into<Point>,
+        I: IntoIterator<Item = (P, T)>,
+    {
+        let mut quadtree = self.build();
+        quadtree.extend(data);
+        quadtree
+    }
+}
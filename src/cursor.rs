@@ -0,0 +1,188 @@
+use crate::{
+    node::NodeRef,
+    point::Point,
+    rectangle::{Quadrant, Rectangle},
+};
+
+/// A read-only cursor pointing at a single node of a [`QuadTree`](crate::QuadTree).
+///
+/// The cursor remembers the path it took from the root,
+/// so it can navigate down into child quadrants as well as back up to parent nodes.
+/// Get one at the root of a tree with [`QuadTree::cursor`](crate::QuadTree::cursor).
+pub struct Cursor<'tree, T> {
+    /// All nodes from the root down to the current node, which is the last element
+    path: Vec<NodeRef<'tree, T>>,
+}
+
+impl<'tree, T> Cursor<'tree, T> {
+    pub(crate) fn new(root: NodeRef<'tree, T>) -> Self {
+        Self { path: vec![root] }
+    }
+
+    fn node(&self) -> NodeRef<'tree, T> {
+        *self.path.last().expect("A cursor always points at a node")
+    }
+
+    /// Move the cursor down into the node stored at the given quadrant.
+    /// Returns `false` and leaves the cursor untouched, if the quadrant holds no node.
+    pub(crate) fn move_to_child(&mut self, quadrant: &Quadrant) -> bool {
+        match self.node().child(quadrant) {
+            Some(node) => {
+                self.path.push(node);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move the cursor up to the parent node.
+    /// Returns `false` and leaves the cursor untouched, if the cursor is at the root.
+    pub(crate) fn move_to_parent(&mut self) -> bool {
+        if self.path.len() > 1 {
+            self.path.pop();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Gets the bounds of the node the cursor points at
+    pub fn bounds(&self) -> &'tree Rectangle {
+        self.node().bounds()
+    }
+
+    /// Gets the depth of the node the cursor points at, the root is at depth 0
+    pub fn depth(&self) -> usize {
+        self.path.len() - 1
+    }
+
+    /// Test if the node the cursor points at has no child nodes
+    pub fn is_leaf(&self) -> bool {
+        self.node().nodes().next().is_none()
+    }
+
+    /// Gets a cursor pointing at the child node stored at the given quadrant.
+    /// Returns `None`, if the quadrant is empty or holds a single data point.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, Quadrant, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 8.0, 8.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(3u8, (1.0, 1.0));
+    /// quadtree.insert(2, (1.0, 2.0));
+    ///
+    /// let root = quadtree.cursor();
+    /// assert!(root.child(Quadrant::TopLeft).is_none());
+    ///
+    /// let child = root.child(Quadrant::BottomLeft).unwrap();
+    /// assert_eq!(1, child.depth());
+    /// assert_eq!(&Rectangle::new((0.0, 0.0), 4.0, 4.0), child.bounds());
+    /// ```
+    pub fn child(&self, quadrant: Quadrant) -> Option<Self> {
+        self.node().child(&quadrant).map(|node| {
+            let mut path = self.path.clone();
+            path.push(node);
+            Self { path }
+        })
+    }
+
+    /// Gets cursors to all child nodes, together with the quadrant they are stored at
+    pub fn children(&self) -> impl Iterator<Item = (Quadrant, Self)> + '_ {
+        Quadrant::ALL
+            .iter()
+            .filter_map(move |q| self.child(*q).map(|c| (*q, c)))
+    }
+
+    /// Gets a cursor pointing at the parent node, or `None` if the cursor is at the root
+    pub fn parent(&self) -> Option<Self> {
+        if self.path.len() > 1 {
+            Some(Self {
+                path: self.path[..self.path.len() - 1].to_vec(),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Gets the bounds of the given quadrant of the node the cursor points at
+    pub fn quadrant_bounds(&self, quadrant: Quadrant) -> Rectangle {
+        self.bounds().create_quadrant(&quadrant)
+    }
+
+    /// Gets the data point stored directly at the given quadrant, if any.
+    /// For trees with a bucket capacity > 1, this is the first data point of the bucket,
+    /// see [`Cursor::quadrant_items`] for all of them.
+    pub fn item(&self, quadrant: Quadrant) -> Option<(&'tree Point, &'tree T)> {
+        self.quadrant_items(quadrant).next()
+    }
+
+    /// Gets all data points stored directly at the given quadrant
+    pub fn quadrant_items(
+        &self,
+        quadrant: Quadrant,
+    ) -> impl Iterator<Item = (&'tree Point, &'tree T)> {
+        self.node()
+            .quadrant(&quadrant)
+            .iter()
+            .flat_map(|tn| tn.data())
+            .map(|data| (data.position(), data.data()))
+    }
+
+    /// Gets all data points stored directly in the node the cursor points at.
+    /// Data stored further down in child nodes is not included.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, Point, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 8.0, 8.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(3u8, (1.0, 1.0));
+    /// quadtree.insert(2, (7.0, 7.0));
+    ///
+    /// let items: Vec<(&Point, &u8)> = quadtree.cursor().items().collect();
+    /// assert_eq!(vec![(&Point::new(1.0, 1.0), &3), (&Point::new(7.0, 7.0), &2)], items);
+    /// ```
+    pub fn items(&self) -> impl Iterator<Item = (&'tree Point, &'tree T)> {
+        self.node()
+            .data()
+            .map(|data| (data.position(), data.data()))
+    }
+}
+
+impl<'tree, T> Clone for Cursor<'tree, T> {
+    fn clone(&self) -> Self {
+        Self {
+            path: self.path.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::arena::Arena;
+
+    #[test]
+    fn cursor_can_move_up_and_down() {
+        // Arrange
+        let mut arena: Arena<u8> = Arena::new_bounded(&Rectangle::new((0.0, 0.0), 8.0, 8.0));
+        arena.insert((0, Point::new(1.0, 1.0)).into());
+        arena.insert((1, Point::new(1.0, 1.5)).into());
+        let mut cursor = Cursor::new(arena.root());
+
+        // Assert
+        assert!(!cursor.move_to_parent());
+        assert!(!cursor.move_to_child(&Quadrant::TopRight));
+        assert!(cursor.move_to_child(&Quadrant::BottomLeft));
+        assert_eq!(1, cursor.depth());
+        assert!(cursor.parent().unwrap().parent().is_none());
+        assert!(cursor.move_to_parent());
+        assert_eq!(0, cursor.depth());
+        assert_eq!(1, cursor.children().count());
+    }
+}
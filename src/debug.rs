@@ -0,0 +1,214 @@
+use crate::{
+    node::NodeRef,
+    point::Point,
+    rectangle::{Quadrant, Rectangle},
+    TreeNode,
+};
+use std::fmt::{self, Debug};
+
+/// Helper to print a [`Node`] together with it's depth inside of the tree
+pub(crate) struct DebugNode<'tree, T> {
+    pub(crate) node: NodeRef<'tree, T>,
+    pub(crate) depth: usize,
+}
+
+impl<'tree, T> Debug for DebugNode<'tree, T>
+where
+    T: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Node")
+            .field("depth", &self.depth)
+            .field("bounds", self.node.bounds())
+            .field("quadrants", &DebugQuadrants(self))
+            .finish()
+    }
+}
+
+/// Prints the occupied quadrants of a node as a map, empty quadrants are skipped
+struct DebugQuadrants<'a, 'tree, T>(&'a DebugNode<'tree, T>);
+
+impl<'a, 'tree, T> Debug for DebugQuadrants<'a, 'tree, T>
+where
+    T: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut quadrants = f.debug_map();
+        for quadrant in Quadrant::ALL.iter() {
+            match self.0.node.quadrant(quadrant) {
+                None => (),
+                Some(TreeNode::Point(data)) => {
+                    quadrants.entry(quadrant, data);
+                }
+                Some(TreeNode::Bucket(bucket)) => {
+                    quadrants.entry(quadrant, bucket);
+                }
+                Some(TreeNode::Node(id)) => {
+                    quadrants.entry(
+                        quadrant,
+                        &DebugNode {
+                            node: self.0.node.get(*id),
+                            depth: self.0.depth + 1,
+                        },
+                    );
+                }
+            }
+        }
+        quadrants.finish()
+    }
+}
+
+impl<'tree, T> Debug for NodeRef<'tree, T>
+where
+    T: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        DebugNode {
+            node: *self,
+            depth: 0,
+        }
+        .fmt(f)
+    }
+}
+
+/// A character grid, onto which node bounds and points are drawn
+struct Canvas {
+    bounds: Rectangle,
+    width: usize,
+    height: usize,
+    cells: Vec<char>,
+}
+
+impl Canvas {
+    fn new(bounds: &Rectangle, width: usize, height: usize) -> Self {
+        Self {
+            bounds: *bounds,
+            width,
+            height,
+            cells: vec![' '; width * height],
+        }
+    }
+
+    fn column(&self, x: f32) -> usize {
+        let t = if self.bounds.width() > 0.0 {
+            (x - self.bounds.min_x()) / self.bounds.width()
+        } else {
+            0.0
+        };
+        ((t * (self.width - 1) as f32).round().max(0.0) as usize).min(self.width - 1)
+    }
+
+    // rows are counted from the top, while our y axis points up
+    fn row(&self, y: f32) -> usize {
+        let t = if self.bounds.height() > 0.0 {
+            (y - self.bounds.min_y()) / self.bounds.height()
+        } else {
+            0.0
+        };
+        let row = ((t * (self.height - 1) as f32).round().max(0.0) as usize).min(self.height - 1);
+        self.height - 1 - row
+    }
+
+    fn cell_mut(&mut self, column: usize, row: usize) -> &mut char {
+        &mut self.cells[row * self.width + column]
+    }
+
+    /// Draw a line character, lines crossing each other become a `+`
+    fn draw_line(&mut self, column: usize, row: usize, c: char) {
+        let cell = self.cell_mut(column, row);
+        *cell = match (*cell, c) {
+            (' ', c) => c,
+            (old, c) if old == c => c,
+            ('*', _) => '*',
+            _ => '+',
+        };
+    }
+
+    fn draw_rectangle(&mut self, rect: &Rectangle) {
+        let left = self.column(rect.min_x());
+        let right = self.column(rect.max_x());
+        let top = self.row(rect.max_y());
+        let bottom = self.row(rect.min_y());
+
+        for column in left..=right {
+            self.draw_line(column, top, '-');
+            self.draw_line(column, bottom, '-');
+        }
+        for row in top..=bottom {
+            self.draw_line(left, row, '|');
+            self.draw_line(right, row, '|');
+        }
+        for (column, row) in [(left, top), (right, top), (left, bottom), (right, bottom)].iter() {
+            *self.cell_mut(*column, *row) = '+';
+        }
+    }
+
+    fn draw_point(&mut self, pt: &Point) {
+        let column = self.column(pt.x());
+        let row = self.row(pt.y());
+        *self.cell_mut(column, row) = '*';
+    }
+
+    fn into_string(self) -> String {
+        let mut text = String::with_capacity((self.width + 1) * self.height);
+        for row in self.cells.chunks(self.width) {
+            text.extend(row.iter());
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// Render node bounds as lines and points as `*` onto a character grid of the given size
+pub(crate) fn render_ascii<'tree>(
+    bounds: &Rectangle,
+    aabbs: &[&Rectangle],
+    points: impl Iterator<Item = &'tree Point>,
+    width: usize,
+    height: usize,
+) -> String {
+    if width == 0 || height == 0 {
+        return String::new();
+    }
+
+    let mut canvas = Canvas::new(bounds, width, height);
+    aabbs.iter().for_each(|aabb| canvas.draw_rectangle(aabb));
+    points.for_each(|pt| canvas.draw_point(pt));
+
+    canvas.into_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::arena::Arena;
+
+    #[test]
+    fn debug_node_should_print_depth_and_quadrants() {
+        // Arrange
+        let mut arena: Arena<u8> = Arena::new_bounded(&Rectangle::new((0.0, 0.0), 10.0, 10.0));
+        arena.insert((1, Point::new(1.0, 1.0)).into());
+        arena.insert((2, Point::new(1.0, 2.0)).into());
+
+        // Act
+        let text = format!("{:?}", arena.root());
+
+        // Assert
+        assert!(text.starts_with("Node { depth: 0"));
+        assert!(text.contains("BottomLeft: Node { depth: 1"));
+        assert!(!text.contains("TopRight"));
+    }
+
+    #[test]
+    fn render_ascii_should_draw_bounds_and_points() {
+        // Arrange
+        let bounds = Rectangle::new((0.0, 0.0), 4.0, 4.0);
+        let points = [Point::new(1.0, 3.0)];
+
+        // Act
+        let text = render_ascii(&bounds, &[&bounds], points.iter(), 5, 5);
+
+        // Assert
+        assert_eq!("+---+\n|*  |\n|   |\n|   |\n+---+\n", text);
+    }
+}
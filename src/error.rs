@@ -0,0 +1,26 @@
+use std::{error::Error, fmt};
+
+/// The reason data could not be inserted into a [`QuadTree`](crate::QuadTree)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum InsertError {
+    /// A coordinate of the position is NaN or infinite
+    NonFinite,
+
+    /// The position is outside of the bounds of the tree
+    OutOfBounds,
+
+    /// The tree already contains data at the position, or within it's tolerance
+    Duplicate,
+}
+
+impl fmt::Display for InsertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InsertError::NonFinite => write!(f, "position has a NaN or infinite coordinate"),
+            InsertError::OutOfBounds => write!(f, "position is outside of the tree bounds"),
+            InsertError::Duplicate => write!(f, "tree already contains data at the position"),
+        }
+    }
+}
+
+impl Error for InsertError {}
@@ -0,0 +1,446 @@
+use crate::{
+    error::InsertError,
+    iter::Iter,
+    nearest::{Nearest, Target},
+    point::Point,
+    quadtree::QuadTree,
+    rectangle::Rectangle,
+};
+
+/// The mean radius of the earth in meters
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// The great-circle distance in meters between two positions given as longitude / latitude in degrees
+///
+/// # Examples
+///
+/// ```
+/// use quadtree::haversine_distance;
+/// let paris = (2.3522, 48.8566);
+/// let london = (-0.1276, 51.5072);
+///
+/// let distance = haversine_distance(paris, london);
+/// assert!((distance - 343_900.0).abs() < 1_000.0);
+/// ```
+pub fn haversine_distance(a: impl Into<Point>, b: impl Into<Point>) -> f32 {
+    let (a, b) = (a.into(), b.into());
+    haversine((a.x() as f64, a.y() as f64), (b.x() as f64, b.y() as f64)) as f32
+}
+
+/// The haversine formula on `(longitude, latitude)` pairs in degrees
+fn haversine(a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (lat_a, lat_b) = (a.1.to_radians(), b.1.to_radians());
+    let half_d_lat = (lat_b - lat_a) / 2.0;
+    let half_d_lon = (b.0 - a.0).to_radians() / 2.0;
+    let h = half_d_lat.sin().powi(2) + lat_a.cos() * lat_b.cos() * half_d_lon.sin().powi(2);
+    2.0 * EARTH_RADIUS * h.sqrt().min(1.0).asin()
+}
+
+/// Test if the longitude is inside of the range from `min` to `max`, modulo full turns
+fn longitude_within(lon: f64, min: f64, max: f64) -> bool {
+    max - min >= 360.0 || (lon - min).rem_euclid(360.0) <= max - min
+}
+
+/// The smallest distance in meters from a position to the meridian at `edge` between two latitudes
+fn meridian_distance(pt: (f64, f64), edge: f64, min_lat: f64, max_lat: f64) -> f64 {
+    let ends = haversine(pt, (edge, min_lat)).min(haversine(pt, (edge, max_lat)));
+
+    // on the facing half of the meridian, the closest point can be between both ends
+    let d_lon = (edge - pt.0).to_radians();
+    if d_lon.cos() > 0.0 {
+        let foot = (pt.1.to_radians().tan() / d_lon.cos()).atan().to_degrees();
+        if foot > min_lat && foot < max_lat {
+            return ends.min(haversine(pt, (edge, foot)));
+        }
+    }
+    ends
+}
+
+/// The smallest distance in meters from a position to any position inside of a longitude / latitude cell.
+///
+/// If the longitude of the position is inside of the cell,
+/// the closest point is straight north or south of it.
+/// Otherwise the closest point is on one of the two meridians bounding the cell,
+/// which includes the poles, if the cell touches them.
+fn cell_distance(pt: &Point, cell: &Rectangle) -> f64 {
+    let pt = (pt.x() as f64, pt.y() as f64);
+    let (min_lon, max_lon) = (cell.min_x() as f64, cell.max_x() as f64);
+    let (min_lat, max_lat) = (cell.min_y() as f64, cell.max_y() as f64);
+
+    if longitude_within(pt.0, min_lon, max_lon) {
+        let d_lat = (min_lat - pt.1).max(pt.1 - max_lat).max(0.0);
+        return EARTH_RADIUS * d_lat.to_radians();
+    }
+
+    meridian_distance(pt, min_lon, min_lat, max_lat)
+        .min(meridian_distance(pt, max_lon, min_lat, max_lat))
+}
+
+/// Great-circle distances in meters to a longitude / latitude position
+struct Geo(Point);
+
+impl Target for Geo {
+    fn distance(&self, pt: &Point) -> f32 {
+        haversine_distance(self.0, *pt)
+    }
+
+    fn lower_bound(&self, bounds: &Rectangle) -> f32 {
+        cell_distance(&self.0, bounds) as f32
+    }
+}
+
+/// A [`QuadTree`] over positions on the earth, given as [`Point`]s of longitude and latitude in degrees.
+///
+/// Nearest neighbor and radius queries use great-circle distances in meters,
+/// so neighbors are correct at high latitudes and across the antimeridian.
+/// Longitudes are in `-180.0..=180.0`, latitudes in `-90.0..=90.0`.
+///
+/// # Examples
+///
+/// ```
+/// use quadtree::GeoQuadTree;
+/// let mut cities = GeoQuadTree::new();
+/// cities.insert("Paris", (2.3522, 48.8566));
+/// cities.insert("London", (-0.1276, 51.5072));
+/// cities.insert("Suva", (178.4419, -18.1416));
+/// cities.insert("Apia", (-171.7514, -13.8333));
+///
+/// assert_eq!(Some(&"Suva"), cities.find_nearest((-179.0, -16.0)));
+/// assert_eq!(vec![&"Paris"], cities.find_in_radius((2.0, 49.0), 50_000.0));
+/// ```
+pub struct GeoQuadTree<T> {
+    tree: QuadTree<T>,
+}
+
+impl<T> GeoQuadTree<T> {
+    /// Creates an empty tree covering the whole globe
+    pub fn new() -> Self {
+        Self {
+            tree: QuadTree::new_bounded(&Rectangle::new((-180.0, -90.0), 360.0, 180.0)),
+        }
+    }
+
+    /// Tries to insert data at a longitude / latitude position, see [`QuadTree::insert`]
+    pub fn insert(&mut self, data: T, position: impl Into<Point>) -> bool {
+        self.tree.insert(data, position)
+    }
+
+    /// Tries to insert data at a longitude / latitude position, returning why it failed if it did,
+    /// see [`QuadTree::try_insert`]
+    pub fn try_insert(&mut self, data: T, position: impl Into<Point>) -> Result<(), InsertError> {
+        self.tree.try_insert(data, position)
+    }
+
+    /// Removes the data stored at the given position, see [`QuadTree::remove`]
+    pub fn remove(&mut self, pt: impl Into<Point>) -> Option<T> {
+        self.tree.remove(pt)
+    }
+
+    /// Test if data is stored at exactly the given position
+    pub fn contains(&self, pt: impl Into<Point>) -> bool {
+        self.tree.contains(pt)
+    }
+
+    /// Gets the data stored at exactly the given position
+    pub fn find_exact(&self, pt: impl Into<Point>) -> Option<&T> {
+        self.tree.find_exact(pt)
+    }
+
+    /// Finds the data with the smallest great-circle distance to the given position
+    ///
+    /// # Arguments
+    ///
+    /// * `pt` - The longitude / latitude position to search from, or anything implementing `Into<Point>`
+    pub fn find_nearest(&self, pt: impl Into<Point>) -> Option<&T> {
+        self.nearest(pt.into()).next().map(|data| data.data())
+    }
+
+    /// Finds the position with the smallest great-circle distance to the given position
+    pub fn find_nearest_position(&self, pt: impl Into<Point>) -> Option<Point> {
+        self.nearest(pt.into()).next().map(|data| *data.position())
+    }
+
+    /// Finds the `k` data points with the smallest great-circle distance to the given position,
+    /// ordered by increasing distance
+    ///
+    /// # Arguments
+    ///
+    /// * `pt` - The longitude / latitude position to search from, or anything implementing `Into<Point>`
+    /// * `k` - The maximum number of data points to return
+    pub fn k_nearest(&self, pt: impl Into<Point>, k: usize) -> Vec<&T> {
+        self.nearest(pt.into())
+            .take(k)
+            .map(|data| data.data())
+            .collect()
+    }
+
+    /// Finds the positions of the `k` data points closest to the given position, see [`GeoQuadTree::k_nearest`]
+    pub fn k_nearest_positions(&self, pt: impl Into<Point>, k: usize) -> Vec<Point> {
+        self.nearest(pt.into())
+            .take(k)
+            .map(|data| *data.position())
+            .collect()
+    }
+
+    /// Finds all data within a great-circle distance of the given position, ordered by increasing distance
+    ///
+    /// # Arguments
+    ///
+    /// * `center` - The longitude / latitude position to search from, or anything implementing `Into<Point>`
+    /// * `meters` - The maximum distance in meters
+    pub fn find_in_radius(&self, center: impl Into<Point>, meters: f32) -> Vec<&T> {
+        self.within(center.into(), meters)
+            .into_iter()
+            .map(|(_, data)| data)
+            .collect()
+    }
+
+    /// Finds the positions of all data within a great-circle distance of the given position,
+    /// see [`GeoQuadTree::find_in_radius`]
+    pub fn find_in_radius_positions(&self, center: impl Into<Point>, meters: f32) -> Vec<Point> {
+        self.within(center.into(), meters)
+            .into_iter()
+            .map(|(position, _)| *position)
+            .collect()
+    }
+
+    /// Finds all data inside of a longitude / latitude box.
+    ///
+    /// Boxes crossing the antimeridian reach past a longitude of `180.0`, or below `-180.0`,
+    /// the part outside of the valid range continues on the other side of the globe.
+    /// Latitudes are clamped to the poles.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{GeoQuadTree, Rectangle};
+    /// let mut cities = GeoQuadTree::new();
+    /// cities.insert("Suva", (178.4419, -18.1416));
+    /// cities.insert("Apia", (-171.7514, -13.8333));
+    /// cities.insert("Sydney", (151.2093, -33.8688));
+    ///
+    /// // from 170° east to 170° west
+    /// let pacific = Rectangle::new((170.0, -25.0), 20.0, 20.0);
+    /// let mut found = cities.find_in_bounds(&pacific);
+    /// found.sort();
+    /// assert_eq!(vec![&"Apia", &"Suva"], found);
+    /// ```
+    pub fn find_in_bounds(&self, bounds: &Rectangle) -> Vec<&T> {
+        Self::split_antimeridian(bounds)
+            .iter()
+            .flat_map(|part| self.tree.find_in_bounds(part).unwrap_or_default())
+            .collect()
+    }
+
+    /// Finds the positions of all data inside of a longitude / latitude box,
+    /// see [`GeoQuadTree::find_in_bounds`]
+    pub fn find_in_bounds_positions(&self, bounds: &Rectangle) -> Vec<Point> {
+        Self::split_antimeridian(bounds)
+            .iter()
+            .flat_map(|part| self.tree.find_in_bounds_positions(part).unwrap_or_default())
+            .collect()
+    }
+
+    /// Returns an iterator over the positions and data of all data points in this tree
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.tree.iter()
+    }
+
+    /// Returns the number of data points in this tree
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Test if the tree holds no data
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    fn nearest(&self, pt: Point) -> Nearest<'_, T, Geo> {
+        Nearest::new(self.tree.root(), Geo(pt))
+    }
+
+    fn within(&self, center: Point, meters: f32) -> Vec<(&Point, &T)> {
+        let mut nearest = self.nearest(center);
+        let mut found = Vec::new();
+        while let Some((distance, data)) = nearest.next_with_distance() {
+            if distance > meters {
+                break;
+            }
+            found.push((data.position(), data.data()));
+        }
+        found
+    }
+
+    /// Split a box into the parts on both sides of the antimeridian, clamped to valid positions
+    fn split_antimeridian(bounds: &Rectangle) -> Vec<Rectangle> {
+        let min_lat = bounds.min_y().max(-90.0);
+        let height = bounds.max_y().min(90.0) - min_lat;
+        if height < 0.0 {
+            return Vec::new();
+        }
+
+        let part = |min_lon: f32, width: f32| {
+            Rectangle::new((min_lon, min_lat), width, height).with_boundary(bounds.boundary())
+        };
+        if bounds.width() >= 360.0 {
+            return vec![part(-180.0, 360.0)];
+        }
+
+        let min_lon = (bounds.min_x() + 180.0).rem_euclid(360.0) - 180.0;
+        let max_lon = min_lon + bounds.width();
+        if max_lon <= 180.0 {
+            vec![part(min_lon, bounds.width())]
+        } else {
+            vec![
+                part(min_lon, 180.0 - min_lon),
+                part(-180.0, max_lon - 180.0),
+            ]
+        }
+    }
+}
+
+impl<T> Default for GeoQuadTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn positions() -> Vec<Point> {
+        // dense around the north pole and the antimeridian, sparse everywhere else
+        let mut positions = Vec::new();
+        for i in 0..36 {
+            positions.push(Point::new(
+                i as f32 * 10.0 - 175.0,
+                88.5 + (i % 3) as f32 * 0.4,
+            ));
+            positions.push(Point::new(
+                179.5 - (i % 6) as f32 * 0.2,
+                i as f32 * 4.0 - 70.0,
+            ));
+            positions.push(Point::new(
+                -179.9 + (i % 4) as f32 * 0.3,
+                i as f32 * 4.0 - 71.0,
+            ));
+            positions.push(Point::new(
+                i as f32 * 9.7 - 170.0,
+                (i * 37 % 160) as f32 - 80.0,
+            ));
+        }
+        positions
+    }
+
+    fn by_distance(positions: &[Point], pt: Point) -> Vec<Point> {
+        let mut sorted = positions.to_vec();
+        sorted.sort_by(|a, b| {
+            haversine_distance(pt, *a)
+                .partial_cmp(&haversine_distance(pt, *b))
+                .unwrap()
+        });
+        sorted
+    }
+
+    #[test]
+    fn geo_cell_distance_is_a_lower_bound() {
+        // Arrange
+        let cells = [
+            Rectangle::new((-180.0, 80.0), 45.0, 10.0),
+            Rectangle::new((170.0, -10.0), 10.0, 20.0),
+            Rectangle::new((0.0, -90.0), 90.0, 45.0),
+            Rectangle::new((-20.0, 40.0), 5.0, 5.0),
+        ];
+
+        for cell in cells.iter() {
+            for pt in positions() {
+                // Act
+                let bound = cell_distance(&pt, cell);
+
+                // Assert
+                for i in 0..=20 {
+                    for j in 0..=20 {
+                        let inside = Point::new(
+                            cell.min_x() + cell.width() * i as f32 / 20.0,
+                            cell.min_y() + cell.height() * j as f32 / 20.0,
+                        );
+                        assert!(bound <= haversine_distance(pt, inside) as f64 + 1.0);
+                    }
+                }
+                if cell.contains(pt) {
+                    assert_eq!(0.0, bound);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn geo_queries_match_brute_force() {
+        // Arrange
+        let positions = positions();
+        let mut tree = GeoQuadTree::new();
+        for (i, position) in positions.iter().enumerate() {
+            assert!(tree.insert(i, *position));
+        }
+
+        for pt in [
+            Point::new(0.0, 90.0),
+            Point::new(-179.99, 12.0),
+            Point::new(179.9, -33.0),
+            Point::new(45.0, 89.0),
+            Point::new(10.0, -10.0),
+        ]
+        .iter()
+        {
+            // Act
+            let nearest = tree.k_nearest_positions(*pt, 10);
+            let within = tree.find_in_radius_positions(*pt, 500_000.0);
+
+            // Assert
+            let expected = by_distance(&positions, *pt);
+            let distances = |points: &[Point]| -> Vec<f32> {
+                points.iter().map(|p| haversine_distance(*pt, *p)).collect()
+            };
+            assert_eq!(distances(&expected[..10]), distances(&nearest));
+            let expected_within: Vec<Point> = expected
+                .into_iter()
+                .filter(|p| haversine_distance(*pt, *p) <= 500_000.0)
+                .collect();
+            assert_eq!(distances(&expected_within), distances(&within));
+        }
+    }
+
+    #[test]
+    fn geo_boxes_wrap_around_the_antimeridian() {
+        // Arrange
+        let positions = positions();
+        let mut tree = GeoQuadTree::new();
+        for (i, position) in positions.iter().enumerate() {
+            tree.insert(i, *position);
+        }
+        let crossing = Rectangle::new((179.0, -20.0), 2.0, 40.0);
+        let negative = Rectangle::new((-181.0, -20.0), 2.0, 40.0);
+
+        // Act
+        let mut found = tree.find_in_bounds(&crossing);
+        let mut also_found = tree.find_in_bounds(&negative);
+
+        // Assert
+        let mut expected: Vec<usize> = positions
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| (p.x() >= 179.0 || p.x() <= -179.0) && p.y() >= -20.0 && p.y() <= 20.0)
+            .map(|(i, _)| i)
+            .collect();
+        expected.sort_unstable();
+        found.sort_unstable();
+        also_found.sort_unstable();
+        assert!(!expected.is_empty());
+        assert_eq!(expected.iter().collect::<Vec<_>>(), found);
+        assert_eq!(found, also_found);
+        assert!(!tree.insert(0, (181.0, 0.0)));
+        assert!(!tree.insert(0, (0.0, -90.5)));
+    }
+}
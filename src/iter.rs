@@ -0,0 +1,58 @@
+use crate::{point::Point, spatial::Spatial};
+use std::vec;
+
+/// An iterator over the positions and data of all data points in a [`QuadTree`](crate::QuadTree),
+/// created by [`QuadTree::iter`](crate::QuadTree::iter)
+pub struct Iter<'tree, T> {
+    data: vec::IntoIter<&'tree Spatial<T>>,
+}
+
+impl<'tree, T> Iter<'tree, T> {
+    pub(crate) fn new(data: Vec<&'tree Spatial<T>>) -> Self {
+        Self {
+            data: data.into_iter(),
+        }
+    }
+}
+
+impl<'tree, T> Iterator for Iter<'tree, T> {
+    type Item = (&'tree Point, &'tree T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.data.next().map(|data| (data.position(), data.data()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.data.size_hint()
+    }
+}
+
+impl<'tree, T> ExactSizeIterator for Iter<'tree, T> {}
+
+/// An owning iterator over the positions and data of all data points in a [`QuadTree`](crate::QuadTree),
+/// created by it's [`IntoIterator`] implementation
+pub struct IntoIter<T> {
+    data: vec::IntoIter<Spatial<T>>,
+}
+
+impl<T> IntoIter<T> {
+    pub(crate) fn new(data: Vec<Spatial<T>>) -> Self {
+        Self {
+            data: data.into_iter(),
+        }
+    }
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = (Point, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.data.next().map(Spatial::into_parts)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.data.size_hint()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
@@ -0,0 +1,46 @@
+use crate::{node::NodeRef, spatial::Spatial, TreeNode};
+
+/// Collect all pairs of data below the nodes `a` and `b` that are at most `distance` apart,
+/// by walking down both trees together.
+///
+/// Node pairs whose bounds are further apart than `distance` are skipped as a whole.
+/// Otherwise the larger one of both nodes is split, data stored directly in it's quadrants
+/// is matched against the whole other node with a radius query.
+pub(crate) fn join_within<'a, 'b, T, U>(
+    a: NodeRef<'a, T>,
+    b: NodeRef<'b, U>,
+    distance: f32,
+    pairs: &mut Vec<(&'a Spatial<T>, &'b Spatial<U>)>,
+) {
+    if a.bounds().squared_distance_to(b.bounds()) > distance * distance {
+        return;
+    }
+
+    if a.bounds().width() >= b.bounds().width() {
+        for tn in a.node().quadrants().flatten() {
+            match tn {
+                TreeNode::Node(id) => join_within(a.get(*id), b, distance, pairs),
+                tn => {
+                    for data in tn.data() {
+                        if let Some(found) = b.find_in_radius(data.position(), distance) {
+                            pairs.extend(found.into_iter().map(|other| (data, other)));
+                        }
+                    }
+                }
+            }
+        }
+    } else {
+        for tn in b.node().quadrants().flatten() {
+            match tn {
+                TreeNode::Node(id) => join_within(a, b.get(*id), distance, pairs),
+                tn => {
+                    for other in tn.data() {
+                        if let Some(found) = a.find_in_radius(other.position(), distance) {
+                            pairs.extend(found.into_iter().map(|data| (data, other)));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
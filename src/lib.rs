@@ -1,20 +1,70 @@
 #![allow(dead_code)]
 
+mod aggregate;
+mod arena;
+mod bucket;
+mod builder;
+mod cursor;
+mod debug;
+mod error;
+mod geo;
+mod iter;
+mod join;
+mod linear;
+mod mesh;
+mod metric;
+mod nearest;
+mod neighbor;
 mod node;
+mod parallel;
+mod persistent;
 mod point;
 mod quadtree;
+mod ray;
 mod rectangle;
 mod spatial;
+mod stats;
+mod svg;
 mod visitor;
 
+#[derive(Clone)]
 pub(crate) enum TreeNode<T>
 where
     T: Sized,
 {
     Point(spatial::Spatial<T>),
-    Node(Box<node::Node<T>>),
+    Bucket(bucket::Bucket<T>),
+    Node(node::NodeId),
 }
 
+impl<T> TreeNode<T>
+where
+    T: Sized,
+{
+    /// Gets all data stored directly in this tree node, which is empty for child nodes
+    pub(crate) fn data(&self) -> &[spatial::Spatial<T>] {
+        match self {
+            TreeNode::Point(data) => std::slice::from_ref(data),
+            TreeNode::Bucket(bucket) => bucket.items(),
+            TreeNode::Node(_) => &[],
+        }
+    }
+}
+
+pub use crate::aggregate::{AggregateQuadTree, Summary};
+pub use crate::builder::QuadTreeBuilder;
+pub use crate::cursor::Cursor;
+pub use crate::error::InsertError;
+pub use crate::geo::{haversine_distance, GeoQuadTree};
+pub use crate::iter::{IntoIter, Iter};
+pub use crate::linear::LinearQuadTree;
+pub use crate::mesh::Mesh;
+pub use crate::metric::{Chebyshev, Euclidean, Manhattan, Metric, Weighted};
+pub use crate::neighbor::{Direction, Leaf};
+pub use crate::persistent::PersistentQuadTree;
 pub use crate::point::Point;
 pub use crate::quadtree::QuadTree;
-pub use crate::rectangle::Rectangle;
+pub use crate::rectangle::{Boundary, Quadrant, Rectangle};
+pub use crate::stats::TreeStats;
+pub use crate::svg::{SvgOptions, SvgQuery};
+pub use crate::visitor::{TreeVisitor, VisitControl};
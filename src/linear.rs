@@ -0,0 +1,551 @@
+use crate::{
+    point::Point,
+    rectangle::{Quadrant, Rectangle, RectangleRelation},
+    spatial::Spatial,
+};
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+/// The number of subdivision levels encoded in a morton key, two bits per level
+const LEVELS: u32 = 32;
+
+/// The number of grid cells along each axis at the deepest level
+const GRID_SIZE: f64 = (1u64 << LEVELS) as f64;
+
+/// Below this number of entries, a cell is scanned directly instead of being subdivided further
+const SCAN_THRESHOLD: usize = 8;
+
+/// # LinearQuadTree
+/// A [linear quadtree](https://en.wikipedia.org/wiki/Z-order_curve), storing all data in a single array,
+/// sorted by the morton code *(or Z-order key)* of it's position.
+///
+/// The key of a position is derived from the tree bounds, by interleaving the quadrant
+/// the position falls into on every level of subdivision, so every quadtree node maps
+/// to a contiguous range of keys. There are no boxed nodes at all, queries binary search
+/// and scan the sorted array instead of chasing pointers.
+pub struct LinearQuadTree<T>
+where
+    T: Sized,
+{
+    bounds: Rectangle,
+    entries: Vec<(u64, Spatial<T>)>,
+}
+
+impl<T> LinearQuadTree<T>
+where
+    T: Sized,
+{
+    /// Creates an empty `LinearQuadTree` with the given bounds
+    ///
+    /// # Arguments
+    ///
+    /// * `bounds` - A [`Rectangle`] by which the quadtree will be bounded
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{LinearQuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let quadtree: LinearQuadTree<u8> = LinearQuadTree::new_bounded(&bounds);
+    /// assert!(quadtree.is_empty());
+    /// ```
+    ///
+    pub const fn new_bounded(bounds: &Rectangle) -> Self {
+        Self {
+            bounds: *bounds,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Creates a `LinearQuadTree` from many data points at once, sorting them only a single time.
+    /// Data points outside of the bounds are skipped, as well as all but the first data point at the same position.
+    ///
+    /// # Arguments
+    ///
+    /// * `bounds` - A [`Rectangle`] by which the quadtree will be bounded
+    /// * `data` - The data points to store in the tree
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{LinearQuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let data = vec![(1u8, (1.0, 1.0).into()), (2, (4.0, 9.0).into()), (3, (1.0, 1.0).into())];
+    /// let quadtree = LinearQuadTree::from_points(&bounds, data);
+    ///
+    /// assert_eq!(2, quadtree.len());
+    /// assert_eq!(Some(&1), quadtree.find_exact((1.0, 1.0)));
+    /// ```
+    ///
+    pub fn from_points(bounds: &Rectangle, data: impl IntoIterator<Item = (T, Point)>) -> Self {
+        let mut tree = Self::new_bounded(bounds);
+        tree.entries = data
+            .into_iter()
+            .filter_map(|(data, position)| {
+                tree.key(&position)
+                    .map(|key| (key, Spatial::from((data, position))))
+            })
+            .collect();
+
+        // a stable sort keeps the first of multiple data points at the same position in front
+        tree.entries.sort_by_key(|(key, _)| *key);
+        tree.entries
+            .dedup_by(|b, a| a.0 == b.0 && a.1.position() == b.1.position());
+
+        tree
+    }
+
+    /// Calculate the morton key of a position, or `None` if it is outside of the tree bounds
+    fn key(&self, pt: &Point) -> Option<u64> {
+        self.bounds.find_quadrant(pt)?;
+
+        let cell = |value: f32, min: f32, size: f32| -> u64 {
+            if size > 0.0 {
+                let t = (value - min) as f64 / size as f64;
+                ((t * GRID_SIZE) as u64).min((1u64 << LEVELS) - 1)
+            } else {
+                0
+            }
+        };
+        let x = cell(pt.x(), self.bounds.min_x(), self.bounds.width());
+        let y = cell(pt.y(), self.bounds.min_y(), self.bounds.height());
+
+        let mut key: u64 = 0;
+        for level in (0..LEVELS).rev() {
+            let quadrant = Quadrant::from_bits(((x >> level) & 1) as u8, ((y >> level) & 1) as u8);
+            key = (key << 2) | digit(&quadrant);
+        }
+
+        Some(key)
+    }
+
+    /// Find the index range of all entries with the given key
+    fn key_range(&self, key: u64) -> (usize, usize) {
+        let start = self.entries.partition_point(|(k, _)| *k < key);
+        let end = self.entries.partition_point(|(k, _)| *k <= key);
+        (start, end)
+    }
+
+    fn position_index(&self, pt: &Point) -> Option<usize> {
+        let (start, end) = self.key_range(self.key(pt)?);
+        (start..end).find(|i| self.entries[*i].1.position() == pt)
+    }
+
+    /// Tries to insert data at the given position, keeping the entries sorted.
+    ///
+    /// # Failure
+    ///
+    /// This function can fail and will return `false` if it did, fail cases are:
+    /// * The position is outside of the bounds of the tree
+    /// * The tree already contains a data point at the given position
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{LinearQuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let mut quadtree = LinearQuadTree::new_bounded(&bounds);
+    ///
+    /// assert!(quadtree.insert(12u8, (0.0, 0.5)));
+    /// assert!(!quadtree.insert(6u8, (0.0, 0.5)));
+    /// assert!(!quadtree.insert(2u8, (-2.0, 5.0)));
+    /// ```
+    ///
+    pub fn insert(&mut self, data: T, position: impl Into<Point>) -> bool {
+        let position = position.into();
+        let key = match self.key(&position) {
+            None => return false,
+            Some(key) => key,
+        };
+
+        let (start, end) = self.key_range(key);
+        if self.entries[start..end]
+            .iter()
+            .any(|(_, s)| *s.position() == position)
+        {
+            return false;
+        }
+
+        self.entries.insert(end, (key, (data, position).into()));
+        true
+    }
+
+    /// Removes the data stored at the given position, giving back ownership to `T`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{LinearQuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let mut quadtree = LinearQuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(12u8, (0.0, 0.5));
+    /// assert_eq!(Some(12), quadtree.remove((0.0, 0.5)));
+    /// assert_eq!(None, quadtree.remove((0.0, 0.5)));
+    /// ```
+    ///
+    pub fn remove(&mut self, pt: impl Into<Point>) -> Option<T> {
+        let index = self.position_index(&pt.into())?;
+        Some(self.entries.remove(index).1.consume())
+    }
+
+    /// Test if the tree contains data at the given position
+    pub fn contains(&self, pt: impl Into<Point>) -> bool {
+        self.position_index(&pt.into()).is_some()
+    }
+
+    /// Finds the data stored at exactly the given position
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{LinearQuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let mut quadtree = LinearQuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(3u8, (2.0, 7.0));
+    /// assert_eq!(Some(&3u8), quadtree.find_exact((2.0, 7.0)));
+    /// assert_eq!(None, quadtree.find_exact((2.001, 6.999)));
+    /// ```
+    ///
+    pub fn find_exact(&self, pt: impl Into<Point>) -> Option<&T> {
+        self.position_index(&pt.into())
+            .map(|i| self.entries[i].1.data())
+    }
+
+    /// The bounds of the cell described by the first `level` quadrant digits of `prefix`
+    fn cell_bounds(&self, level: u32, prefix: u64) -> Rectangle {
+        let (mut x, mut y) = (0u64, 0u64);
+        for i in (0..level).rev() {
+            let d = (prefix >> (2 * i)) & 3;
+            x = (x << 1) | (d & 1);
+            y = (y << 1) | (d >> 1);
+        }
+
+        let width = self.bounds.width() / (1u64 << level) as f32;
+        let height = self.bounds.height() / (1u64 << level) as f32;
+        Rectangle::new(
+            (
+                self.bounds.min_x() + x as f32 * width,
+                self.bounds.min_y() + y as f32 * height,
+            ),
+            width,
+            height,
+        )
+    }
+
+    /// Split the entry range of a cell into the ranges of it's four child cells, in key order
+    fn child_ranges(
+        &self,
+        level: u32,
+        prefix: u64,
+        start: usize,
+        end: usize,
+    ) -> [(u64, usize, usize); 4] {
+        let shift = 2 * (LEVELS - level - 1);
+        let mut ranges = [(0, 0, 0); 4];
+        let mut lower = start;
+        for (d, range) in ranges.iter_mut().enumerate() {
+            let child = (prefix << 2) | d as u64;
+            let upper = if d == 3 {
+                end
+            } else {
+                let next_key = (child + 1) << shift;
+                lower + self.entries[lower..end].partition_point(|(k, _)| *k < next_key)
+            };
+            *range = (child, lower, upper);
+            lower = upper;
+        }
+        ranges
+    }
+
+    fn collect_in_bounds<'a>(
+        &'a self,
+        bounds: &Rectangle,
+        level: u32,
+        prefix: u64,
+        start: usize,
+        end: usize,
+        data: &mut Vec<&'a Spatial<T>>,
+    ) {
+        if start == end {
+            return;
+        }
+
+        let scan = |data: &mut Vec<&'a Spatial<T>>| {
+            data.extend(
+                self.entries[start..end]
+                    .iter()
+                    .map(|(_, s)| s)
+                    .filter(|s| bounds.find_quadrant(s.position()).is_some()),
+            )
+        };
+
+        if level == LEVELS || end - start <= SCAN_THRESHOLD {
+            scan(data);
+            return;
+        }
+
+        match self.cell_bounds(level, prefix).relation(bounds) {
+            RectangleRelation::Disjoint => (),
+            RectangleRelation::Containment(false) => {
+                data.extend(self.entries[start..end].iter().map(|(_, s)| s))
+            }
+            _ => {
+                for (child, lower, upper) in self.child_ranges(level, prefix, start, end).iter() {
+                    self.collect_in_bounds(bounds, level + 1, *child, *lower, *upper, data);
+                }
+            }
+        }
+    }
+
+    fn spatial_in_bounds(&self, bounds: &Rectangle) -> Option<Vec<&Spatial<T>>> {
+        if self.bounds.relation(bounds) == RectangleRelation::Disjoint {
+            return None;
+        }
+
+        let mut data = Vec::new();
+        self.collect_in_bounds(bounds, 0, 0, 0, self.entries.len(), &mut data);
+        Some(data)
+    }
+
+    /// Finds all data contained in the given rectangle bounds, in key order
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{LinearQuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let mut quadtree = LinearQuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(3u8, (2.0, 7.0));
+    /// quadtree.insert(2, (2.5, 7.5));
+    /// quadtree.insert(1, (1.0, 5.5));
+    ///
+    /// let bounds = Rectangle::new((2.0, 6.0), 4.0, 5.0);
+    /// assert_eq!(Some(vec![&3u8, &2]), quadtree.find_in_bounds(&bounds));
+    /// ```
+    ///
+    pub fn find_in_bounds(&self, bounds: &Rectangle) -> Option<Vec<&T>> {
+        self.spatial_in_bounds(bounds)
+            .map(|data| data.iter().map(|d| d.data()).collect())
+    }
+
+    /// Finds all positions contained in the given rectangle bounds, in key order
+    pub fn find_in_bounds_positions(&self, bounds: &Rectangle) -> Option<Vec<Point>> {
+        self.spatial_in_bounds(bounds)
+            .map(|data| data.iter().map(|d| *d.position()).collect())
+    }
+
+    /// Best-first search over the implicit cells, closest cells and entries are expanded first
+    fn nearest(&self, pt: &Point) -> Option<&Spatial<T>> {
+        let mut queue = BinaryHeap::new();
+        queue.push(Candidate {
+            distance: 0.0,
+            item: Item::Cell(0, 0, 0, self.entries.len()),
+        });
+
+        while let Some(Candidate { item, .. }) = queue.pop() {
+            match item {
+                Item::Entry(index) => return Some(&self.entries[index].1),
+                Item::Cell(_, _, start, end) if start == end => (),
+                Item::Cell(level, prefix, start, end) => {
+                    if level == LEVELS || end - start <= SCAN_THRESHOLD {
+                        for index in start..end {
+                            queue.push(Candidate {
+                                distance: self.entries[index].1.position().squared_distance(pt),
+                                item: Item::Entry(index),
+                            });
+                        }
+                    } else {
+                        for (child, lower, upper) in
+                            self.child_ranges(level, prefix, start, end).iter()
+                        {
+                            if lower == upper {
+                                continue;
+                            }
+                            queue.push(Candidate {
+                                distance: self.cell_bounds(level + 1, *child).squared_distance(pt),
+                                item: Item::Cell(level + 1, *child, *lower, *upper),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Finds the data of the nearest neighbor to a given test point
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{LinearQuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let mut quadtree = LinearQuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(3u8, (2.0, 7.0));
+    /// quadtree.insert(2, (2.5, 7.5));
+    ///
+    /// assert_eq!(Some(&2u8), quadtree.find_nearest_neighbor((3.0, 8.0)));
+    /// ```
+    ///
+    pub fn find_nearest_neighbor(&self, pt: impl Into<Point>) -> Option<&T> {
+        self.nearest(&pt.into()).map(|s| s.data())
+    }
+
+    /// Finds the position of the nearest neighbor to a given test point
+    pub fn find_nearest_neighbor_position(&self, pt: impl Into<Point>) -> Option<Point> {
+        self.nearest(&pt.into()).map(|s| *s.position())
+    }
+
+    /// Get the number of data points stored in this tree
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Test if the tree contains no data points at all
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Gets the bounds of the tree
+    pub fn bounds(&self) -> &Rectangle {
+        &self.bounds
+    }
+
+    /// Returns an iterator over the sorted morton keys of all data points
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{LinearQuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 4.0, 4.0);
+    /// let mut quadtree = LinearQuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(3u8, (3.0, 3.0));
+    /// quadtree.insert(2, (1.0, 1.0));
+    /// quadtree.insert(1, (3.0, 1.0));
+    ///
+    /// // bottom left, bottom right and top right quadrant, in this order
+    /// let top_levels: Vec<u64> = quadtree.keys().map(|k| k >> 62).collect();
+    /// assert_eq!(vec![0, 1, 3], top_levels);
+    /// ```
+    ///
+    pub fn keys(&self) -> impl Iterator<Item = u64> + '_ {
+        self.entries.iter().map(|(key, _)| *key)
+    }
+
+    /// Returns an iterator over the positions and data of all data points, in key order
+    pub fn iter(&self) -> impl Iterator<Item = (&Point, &T)> {
+        self.entries.iter().map(|(_, s)| (s.position(), s.data()))
+    }
+
+    /// Returns an iterator over the positions of all data points, in key order
+    pub fn iter_positions(&self) -> impl Iterator<Item = &Point> {
+        self.entries.iter().map(|(_, s)| s.position())
+    }
+
+    /// Returns an iterator over the data of all data points, in key order
+    pub fn iter_data(&self) -> impl Iterator<Item = &T> {
+        self.entries.iter().map(|(_, s)| s.data())
+    }
+}
+
+/// The z-order digit of a quadrant, the vertical half is the more significant bit
+fn digit(quadrant: &Quadrant) -> u64 {
+    ((quadrant.y_bit() << 1) | quadrant.x_bit()) as u64
+}
+
+/// Something to expand during the nearest neighbor search
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Item {
+    /// A cell given by it's level, key prefix and entry range
+    Cell(u32, u64, usize, usize),
+    /// A single entry given by it's index
+    Entry(usize),
+}
+
+/// An item in the nearest neighbor queue, ordered so the closest item is popped first
+#[derive(Debug, PartialEq)]
+struct Candidate {
+    distance: f32,
+    item: Item,
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // reversed, because the binary heap is a max-heap
+        other
+            .distance
+            .partial_cmp(&self.distance)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn grid_tree() -> LinearQuadTree<usize> {
+        let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+        let mut tree = LinearQuadTree::new_bounded(&bounds);
+        for i in 0..10 {
+            for j in 0..10 {
+                tree.insert(i * 10 + j, (i as f32 + 0.5, j as f32 + 0.5));
+            }
+        }
+        tree
+    }
+
+    #[test]
+    fn linear_keys_are_sorted() {
+        // Arrange
+        let tree = grid_tree();
+        let keys: Vec<u64> = tree.keys().collect();
+
+        // Assert
+        assert_eq!(100, tree.len());
+        assert!(keys.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(
+            Rectangle::new((5.0, 0.0), 5.0, 5.0),
+            tree.cell_bounds(1, digit(&Quadrant::BottomRight))
+        );
+    }
+
+    #[test]
+    fn linear_can_query_by_bounds() {
+        // Arrange
+        let tree = grid_tree();
+
+        // Assert
+        let query_rect = Rectangle::new((5.0, 5.0), 5.6, 5.6);
+        assert_eq!(25, tree.find_in_bounds(&query_rect).unwrap().len());
+
+        let query_rect = Rectangle::new((2.2, 3.2), 2.0, 0.5);
+        assert_eq!(Some(vec![&23, &33]), tree.find_in_bounds(&query_rect));
+    }
+
+    #[test]
+    fn linear_finds_nearest_neighbor() {
+        // Arrange
+        let mut tree = grid_tree();
+
+        // Assert
+        assert_eq!(Some(&34), tree.find_nearest_neighbor((3.4, 4.6)));
+        assert_eq!(Some(34), tree.remove((3.5, 4.5)));
+        assert_eq!(
+            Some(Point::new(3.5, 3.5)),
+            tree.find_nearest_neighbor_position((3.4, 4.0))
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use crate::{
+    neighbor::{self, Direction, Leaf, LeafPath},
+    node::NodeRef,
+    point::Point,
+    rectangle::Rectangle,
+};
+use std::collections::HashMap;
+
+/// A triangle mesh over the leaves of a [`QuadTree`](crate::QuadTree),
+/// as created by [`QuadTree::mesh`](crate::QuadTree::mesh).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mesh {
+    /// The vertex buffer, every vertex is unique
+    pub vertices: Vec<Point>,
+
+    /// The index buffer, every three consecutive indices into `vertices` form a counter-clockwise triangle
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    /// Gets the number of triangles in the mesh
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+}
+
+/// Find all leaves which violate the 2:1 balance condition, because a leaf sharing an edge
+/// with them is subdivided more than one level deeper.
+/// Returns the centers of the violating leaves.
+pub(crate) fn unbalanced_leaves<T>(root: NodeRef<'_, T>) -> Vec<Point> {
+    neighbor::leaves(root)
+        .iter()
+        .filter(|leaf| {
+            let center = leaf.bounds().center();
+            let path = LeafPath::find(root, &center).expect("Leaf center has to be in bounds");
+            Direction::CARDINAL.iter().any(|direction| {
+                path.neighbors(direction)
+                    .iter()
+                    .any(|n| n.depth() > leaf.depth() + 1)
+            })
+        })
+        .map(|leaf| leaf.bounds().center())
+        .collect()
+}
+
+/// Deduplicates vertices on a grid fine enough to hold the corners and centers of the deepest leaves
+struct VertexBuffer {
+    bounds: Rectangle,
+    resolution: f64,
+    lookup: HashMap<(i64, i64), u32>,
+    vertices: Vec<Point>,
+}
+
+impl VertexBuffer {
+    fn new(bounds: &Rectangle, max_depth: usize) -> Self {
+        Self {
+            bounds: *bounds,
+            resolution: 2f64.powi(max_depth as i32 + 1),
+            lookup: HashMap::new(),
+            vertices: Vec::new(),
+        }
+    }
+
+    fn grid(&self, value: f32, min: f32, size: f32) -> i64 {
+        if size > 0.0 {
+            ((value - min) as f64 / size as f64 * self.resolution).round() as i64
+        } else {
+            0
+        }
+    }
+
+    fn index(&mut self, pt: Point) -> u32 {
+        let key = (
+            self.grid(pt.x(), self.bounds.min_x(), self.bounds.width()),
+            self.grid(pt.y(), self.bounds.min_y(), self.bounds.height()),
+        );
+        let vertices = &mut self.vertices;
+        *self.lookup.entry(key).or_insert_with(|| {
+            vertices.push(pt);
+            (vertices.len() - 1) as u32
+        })
+    }
+}
+
+/// Create a conforming triangle mesh over all leaves below `root`.
+///
+/// Every leaf is triangulated on it's own. Leaves without finer neighbors are split into two triangles,
+/// leaves with hanging vertices on their edges are triangulated as a fan around their center.
+pub(crate) fn triangulate<T>(root: NodeRef<'_, T>) -> Mesh {
+    let leaves = neighbor::leaves(root);
+    let max_depth = leaves.iter().map(|l| l.depth()).max().unwrap_or(0);
+    let mut buffer = VertexBuffer::new(root.bounds(), max_depth);
+    let mut indices: Vec<u32> = Vec::new();
+
+    for leaf in leaves.iter() {
+        let boundary: Vec<u32> = boundary_vertices(root, leaf)
+            .into_iter()
+            .map(|pt| buffer.index(pt))
+            .collect();
+
+        if boundary.len() == 4 {
+            indices.extend_from_slice(&[boundary[0], boundary[1], boundary[2]]);
+            indices.extend_from_slice(&[boundary[0], boundary[2], boundary[3]]);
+        } else {
+            let center = buffer.index(leaf.bounds().center());
+            for (i, a) in boundary.iter().enumerate() {
+                let b = boundary[(i + 1) % boundary.len()];
+                indices.extend_from_slice(&[center, *a, b]);
+            }
+        }
+    }
+
+    Mesh {
+        vertices: buffer.vertices,
+        indices,
+    }
+}
+
+/// Collect the corners of a leaf and all hanging vertices on it's edges,
+/// in counter-clockwise order starting at the bottom left corner
+fn boundary_vertices<T>(root: NodeRef<'_, T>, leaf: &Leaf<'_, T>) -> Vec<Point> {
+    let bounds = leaf.bounds();
+    let path = LeafPath::find(root, &bounds.center()).expect("Leaf center has to be in bounds");
+    let [bottom_left, bottom_right, top_right, top_left] = bounds.corners();
+
+    // the corners of finer neighbors, which lie on the given edge of this leaf
+    let hanging = |direction: Direction, horizontal: bool, descending: bool| {
+        let mut values: Vec<f32> = path
+            .neighbors(&direction)
+            .iter()
+            .filter(|n| n.depth() > leaf.depth())
+            .flat_map(|n| {
+                let b = n.bounds();
+                if horizontal {
+                    vec![b.min_x(), b.max_x()]
+                } else {
+                    vec![b.min_y(), b.max_y()]
+                }
+            })
+            .filter(|v| {
+                if horizontal {
+                    *v > bounds.min_x() && *v < bounds.max_x()
+                } else {
+                    *v > bounds.min_y() && *v < bounds.max_y()
+                }
+            })
+            .collect();
+        values.sort_by(|a, b| a.partial_cmp(b).expect("Could not order vertices"));
+        values.dedup();
+        if descending {
+            values.reverse();
+        }
+        values
+    };
+
+    let mut vertices = vec![bottom_left];
+    for x in hanging(Direction::South, true, false) {
+        vertices.push(Point::new(x, bounds.min_y()));
+    }
+    vertices.push(bottom_right);
+    for y in hanging(Direction::East, false, false) {
+        vertices.push(Point::new(bounds.max_x(), y));
+    }
+    vertices.push(top_right);
+    for x in hanging(Direction::North, true, true) {
+        vertices.push(Point::new(x, bounds.max_y()));
+    }
+    vertices.push(top_left);
+    for y in hanging(Direction::West, false, true) {
+        vertices.push(Point::new(bounds.min_x(), y));
+    }
+
+    vertices
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::arena::Arena;
+
+    #[test]
+    fn triangulate_unsplit_node() {
+        // Arrange
+        let arena: Arena<u8> = Arena::new_bounded(&Rectangle::new((0.0, 0.0), 2.0, 2.0));
+
+        // Act
+        let mesh = triangulate(arena.root());
+
+        // Assert
+        assert_eq!(9, mesh.vertices.len());
+        assert_eq!(8, mesh.triangle_count());
+    }
+
+    #[test]
+    fn triangulate_with_hanging_vertices() {
+        // Arrange
+        let mut arena: Arena<u8> = Arena::new_bounded(&Rectangle::new((0.0, 0.0), 4.0, 4.0));
+        arena.subdivide_leaf_at(&Point::new(1.0, 1.0));
+
+        // Act
+        let mesh = triangulate(arena.root());
+
+        // Assert
+        // the bottom left quadrant is split in 4 leaves with 2 triangles each,
+        // the bottom right and top left quadrants have a hanging vertex and are fanned into 5 triangles,
+        // the top right quadrant has no finer neighbors and is split into 2 triangles
+        assert_eq!(8 + 5 + 5 + 2, mesh.triangle_count());
+        assert_eq!(9 + 5 + 2, mesh.vertices.len());
+    }
+
+    #[test]
+    fn unbalanced_leaves_are_found() {
+        // Arrange
+        let mut arena: Arena<u8> = Arena::new_bounded(&Rectangle::new((0.0, 0.0), 8.0, 8.0));
+        arena.subdivide_leaf_at(&Point::new(3.5, 3.5));
+        arena.subdivide_leaf_at(&Point::new(3.5, 3.5));
+
+        // Act
+        let unbalanced = unbalanced_leaves(arena.root());
+
+        // Assert
+        assert_eq!(vec![Point::new(6.0, 2.0), Point::new(2.0, 6.0)], unbalanced);
+    }
+}
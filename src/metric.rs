@@ -0,0 +1,244 @@
+use crate::{nearest::Target, point::Point, rectangle::Rectangle};
+
+/// A distance metric for nearest neighbor and radius queries,
+/// see [`QuadTree::k_nearest_by`](crate::QuadTree::k_nearest_by).
+///
+/// # Examples
+///
+/// ```
+/// use quadtree::{Metric, Point, Rectangle};
+///
+/// /// Only horizontal distances count
+/// struct Horizontal;
+///
+/// impl Metric for Horizontal {
+///     fn distance(&self, a: &Point, b: &Point) -> f32 {
+///         (a.x() - b.x()).abs()
+///     }
+///
+///     fn lower_bound(&self, pt: &Point, bounds: &Rectangle) -> f32 {
+///         (bounds.min_x() - pt.x()).max(pt.x() - bounds.max_x()).max(0.0)
+///     }
+/// }
+///
+/// let bounds = Rectangle::new((2.0, 0.0), 1.0, 1.0);
+/// assert_eq!(3.0, Horizontal.distance(&Point::new(1.0, 1.0), &Point::new(4.0, 8.0)));
+/// assert_eq!(1.0, Horizontal.lower_bound(&Point::new(1.0, 5.0), &bounds));
+/// ```
+pub trait Metric {
+    /// The distance between two positions
+    fn distance(&self, a: &Point, b: &Point) -> f32;
+
+    /// A lower bound of the distance from `pt` to every position inside of `bounds`.
+    /// It must never be larger than the actual distance, `0.0` is always a valid but slow choice.
+    fn lower_bound(&self, pt: &Point, bounds: &Rectangle) -> f32;
+}
+
+/// The horizontal and vertical gap between a point and the closest point of a rectangle
+fn gaps(pt: &Point, bounds: &Rectangle) -> (f32, f32) {
+    let dx = (bounds.min_x() - pt.x())
+        .max(pt.x() - bounds.max_x())
+        .max(0.0);
+    let dy = (bounds.min_y() - pt.y())
+        .max(pt.y() - bounds.max_y())
+        .max(0.0);
+    (dx, dy)
+}
+
+/// The straight line distance
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Euclidean;
+
+impl Metric for Euclidean {
+    fn distance(&self, a: &Point, b: &Point) -> f32 {
+        a.squared_distance(b).sqrt()
+    }
+
+    fn lower_bound(&self, pt: &Point, bounds: &Rectangle) -> f32 {
+        bounds.squared_distance(pt).sqrt()
+    }
+}
+
+/// The sum of the horizontal and vertical distance, as on a grid
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Manhattan;
+
+impl Metric for Manhattan {
+    fn distance(&self, a: &Point, b: &Point) -> f32 {
+        (a.x() - b.x()).abs() + (a.y() - b.y()).abs()
+    }
+
+    fn lower_bound(&self, pt: &Point, bounds: &Rectangle) -> f32 {
+        let (dx, dy) = gaps(pt, bounds);
+        dx + dy
+    }
+}
+
+/// The larger one of the horizontal and vertical distance
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Chebyshev;
+
+impl Metric for Chebyshev {
+    fn distance(&self, a: &Point, b: &Point) -> f32 {
+        (a.x() - b.x()).abs().max((a.y() - b.y()).abs())
+    }
+
+    fn lower_bound(&self, pt: &Point, bounds: &Rectangle) -> f32 {
+        let (dx, dy) = gaps(pt, bounds);
+        dx.max(dy)
+    }
+}
+
+/// The euclidean distance with the horizontal and vertical distance scaled by separate weights
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Weighted {
+    x: f32,
+    y: f32,
+}
+
+impl Weighted {
+    /// Creates an anisotropic euclidean metric
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The factor for horizontal distances, negative weights are treated as their absolute value
+    /// * `y` - The factor for vertical distances, negative weights are treated as their absolute value
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{Metric, Point, Weighted};
+    /// let metric = Weighted::new(1.0, 2.0);
+    ///
+    /// assert_eq!(5.0, metric.distance(&Point::new(0.0, 0.0), &Point::new(3.0, 2.0)));
+    /// ```
+    pub fn new(x: f32, y: f32) -> Self {
+        Self {
+            x: x.abs(),
+            y: y.abs(),
+        }
+    }
+
+    fn scaled(&self, dx: f32, dy: f32) -> f32 {
+        ((self.x * dx).powi(2) + (self.y * dy).powi(2)).sqrt()
+    }
+}
+
+impl Metric for Weighted {
+    fn distance(&self, a: &Point, b: &Point) -> f32 {
+        self.scaled(a.x() - b.x(), a.y() - b.y())
+    }
+
+    fn lower_bound(&self, pt: &Point, bounds: &Rectangle) -> f32 {
+        let (dx, dy) = gaps(pt, bounds);
+        self.scaled(dx, dy)
+    }
+}
+
+/// Distances to a point under a [`Metric`], optionally in a space wrapping around at it's edges
+pub(crate) struct Measured<'m, M: ?Sized> {
+    pt: Point,
+    metric: &'m M,
+
+    /// the bounds of a wrapping space, see [`QuadTreeBuilder::wrapping`](crate::QuadTreeBuilder::wrapping)
+    space: Option<Rectangle>,
+}
+
+impl<'m, M: Metric + ?Sized> Measured<'m, M> {
+    pub(crate) fn new(pt: Point, metric: &'m M, space: Option<Rectangle>) -> Self {
+        let pt = match &space {
+            None => pt,
+            Some(space) => space.wrap_point(&pt),
+        };
+        Self { pt, metric, space }
+    }
+}
+
+/// Shift a coordinate by the circumference towards `center`, if that is closer
+fn closest_image(x: f32, center: f32, circumference: f32) -> f32 {
+    if x - center > circumference / 2.0 {
+        x - circumference
+    } else if center - x > circumference / 2.0 {
+        x + circumference
+    } else {
+        x
+    }
+}
+
+impl<'m, M: Metric + ?Sized> Target for Measured<'m, M> {
+    fn distance(&self, pt: &Point) -> f32 {
+        match &self.space {
+            None => self.metric.distance(&self.pt, pt),
+            Some(space) => {
+                let image = Point::new(
+                    closest_image(pt.x(), self.pt.x(), space.width()),
+                    closest_image(pt.y(), self.pt.y(), space.height()),
+                );
+                self.metric.distance(&self.pt, &image)
+            }
+        }
+    }
+
+    fn lower_bound(&self, bounds: &Rectangle) -> f32 {
+        match &self.space {
+            None => self.metric.lower_bound(&self.pt, bounds),
+            Some(space) => {
+                let (width, height) = (space.width(), space.height());
+                let mut bound = f32::INFINITY;
+                for shift_x in [-width, 0.0, width].iter() {
+                    for shift_y in [-height, 0.0, height].iter() {
+                        let image = Rectangle::new(
+                            (bounds.min_x() + shift_x, bounds.min_y() + shift_y),
+                            bounds.width(),
+                            bounds.height(),
+                        );
+                        bound = bound.min(self.metric.lower_bound(&self.pt, &image));
+                    }
+                }
+                bound
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn metric_lower_bounds_never_exceed_distances() {
+        // Arrange
+        let metrics: [&dyn Metric; 4] =
+            [&Euclidean, &Manhattan, &Chebyshev, &Weighted::new(0.5, 3.0)];
+        let bounds = Rectangle::new((2.0, 3.0), 4.0, 2.0);
+        let inside: Vec<Point> = (0..=8)
+            .flat_map(|i| {
+                (0..=4).map(move |j| Point::new(2.0 + i as f32 * 0.5, 3.0 + j as f32 * 0.5))
+            })
+            .collect();
+        let tests = [
+            Point::new(0.0, 0.0),
+            Point::new(4.0, 4.0),
+            Point::new(7.0, 4.0),
+            Point::new(9.0, 9.0),
+        ];
+
+        for metric in metrics.iter() {
+            for test in tests.iter() {
+                // Act
+                let bound = metric.lower_bound(test, &bounds);
+
+                // Assert
+                let closest = inside
+                    .iter()
+                    .map(|pt| metric.distance(test, pt))
+                    .fold(f32::INFINITY, f32::min);
+                assert!(bound <= closest + 1e-5);
+                assert!(
+                    bound >= closest - 1e-5,
+                    "lower bound should be tight at corners and edges"
+                );
+            }
+        }
+    }
+}
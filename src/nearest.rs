@@ -0,0 +1,359 @@
+use crate::{node::NodeRef, point::Point, rectangle::Rectangle, spatial::Spatial, TreeNode};
+use std::{cell::Cell, cmp::Ordering, collections::BinaryHeap, f32::consts::PI};
+
+/// What the nearest neighbor search measures distances to
+pub(crate) trait Target {
+    /// The distance of a stored position to the target
+    fn distance(&self, pt: &Point) -> f32;
+
+    /// A lower bound of the distance of every position inside of `bounds` to the target,
+    /// infinity if no position inside of `bounds` should be found at all
+    fn lower_bound(&self, bounds: &Rectangle) -> f32;
+}
+
+/// Plain points are compared by their squared euclidean distance
+impl Target for Point {
+    fn distance(&self, pt: &Point) -> f32 {
+        pt.squared_distance(self)
+    }
+
+    fn lower_bound(&self, bounds: &Rectangle) -> f32 {
+        bounds.squared_distance(self)
+    }
+}
+
+/// Squared euclidean distances to a point, in a space which wraps around at it's edges like a torus
+pub(crate) struct Wrapping {
+    pt: Point,
+    width: f32,
+    height: f32,
+}
+
+impl Wrapping {
+    pub(crate) fn new(pt: Point, space: &Rectangle) -> Self {
+        Self {
+            pt: space.wrap_point(&pt),
+            width: space.width(),
+            height: space.height(),
+        }
+    }
+}
+
+/// The distance of two coordinates on a circle with the given circumference
+fn wrapped(a: f32, b: f32, circumference: f32) -> f32 {
+    let d = (a - b).abs();
+    d.min(circumference - d)
+}
+
+/// The distance of a coordinate to an interval on a circle with the given circumference
+fn wrapped_to_interval(x: f32, min: f32, max: f32, circumference: f32) -> f32 {
+    if x < min {
+        (min - x).min((x + circumference - max).max(0.0))
+    } else if x > max {
+        (x - max).min((min - (x - circumference)).max(0.0))
+    } else {
+        0.0
+    }
+}
+
+impl Target for Wrapping {
+    fn distance(&self, pt: &Point) -> f32 {
+        let dx = wrapped(pt.x(), self.pt.x(), self.width);
+        let dy = wrapped(pt.y(), self.pt.y(), self.height);
+        dx * dx + dy * dy
+    }
+
+    fn lower_bound(&self, bounds: &Rectangle) -> f32 {
+        let dx = wrapped_to_interval(self.pt.x(), bounds.min_x(), bounds.max_x(), self.width);
+        let dy = wrapped_to_interval(self.pt.y(), bounds.min_y(), bounds.max_y(), self.height);
+        dx * dx + dy * dy
+    }
+}
+
+/// Orders by decreasing squared euclidean distance to a point, by negating the distances
+pub(crate) struct Farthest(pub(crate) Point);
+
+impl Target for Farthest {
+    fn distance(&self, pt: &Point) -> f32 {
+        -pt.squared_distance(&self.0)
+    }
+
+    fn lower_bound(&self, bounds: &Rectangle) -> f32 {
+        -bounds.max_squared_distance(&self.0)
+    }
+}
+
+/// The angle of `pt` around `origin` in `[0, 2π]`, counter-clockwise from the positive x axis
+fn angle(origin: &Point, pt: &Point) -> f32 {
+    (pt.y() - origin.y())
+        .atan2(pt.x() - origin.x())
+        .rem_euclid(2.0 * PI)
+}
+
+/// The index of the 60° sector containing the given angle
+fn sector_of(angle: f32) -> usize {
+    ((angle / (PI / 3.0)) as usize).min(5)
+}
+
+/// The index of the 60° sector around `origin` which contains `pt`, counter-clockwise from the positive x axis
+pub(crate) fn sector(origin: &Point, pt: &Point) -> usize {
+    sector_of(angle(origin, pt))
+}
+
+/// Squared euclidean distances to a point, skipping nodes which only overlap sectors marked as full
+pub(crate) struct Sectors<'a> {
+    pub(crate) pt: Point,
+    pub(crate) full: &'a Cell<[bool; 6]>,
+}
+
+impl<'a> Target for Sectors<'a> {
+    fn distance(&self, pt: &Point) -> f32 {
+        pt.squared_distance(&self.pt)
+    }
+
+    fn lower_bound(&self, bounds: &Rectangle) -> f32 {
+        let distance = bounds.squared_distance(&self.pt);
+        if distance <= 0.0 {
+            return distance;
+        }
+
+        // seen from outside, the rectangle spans less than a half turn,
+        // from the corner all other corners are less than a half turn counter-clockwise of
+        let angles: Vec<f32> = bounds
+            .corners()
+            .iter()
+            .map(|corner| angle(&self.pt, corner))
+            .collect();
+        let turned = |from: f32, to: f32| (to - from).rem_euclid(2.0 * PI);
+        let start = match angles
+            .iter()
+            .find(|from| angles.iter().all(|to| turned(**from, *to) < PI))
+        {
+            Some(start) => *start,
+            // rounding errors right next to the rectangle, never skip it then
+            None => return distance,
+        };
+        let end = angles.iter().fold(start, |end, to| {
+            if turned(start, *to) > turned(start, end) {
+                *to
+            } else {
+                end
+            }
+        });
+        let start = sector_of(start);
+        let length = (sector_of(end) + 6 - start) % 6;
+
+        let full = self.full.get();
+        if (start..=start + length).all(|s| full[s % 6]) {
+            f32::INFINITY
+        } else {
+            distance
+        }
+    }
+}
+
+/// Something to expand during the nearest neighbor search
+enum Item<'tree, T> {
+    Node(NodeRef<'tree, T>),
+    Data(&'tree Spatial<T>),
+}
+
+/// An item in the nearest neighbor queue, ordered so the closest item is popped first
+struct Candidate<'tree, T> {
+    distance: f32,
+    item: Item<'tree, T>,
+}
+
+impl<'tree, T> PartialEq for Candidate<'tree, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<'tree, T> Eq for Candidate<'tree, T> {}
+
+impl<'tree, T> PartialOrd for Candidate<'tree, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'tree, T> Ord for Candidate<'tree, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // reversed, because the binary heap is a max-heap
+        other
+            .distance
+            .partial_cmp(&self.distance)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+/// A best-first search below a node, yielding the stored data ordered by increasing distance
+/// to a target, usually a test point.
+///
+/// Nodes are only expanded once they are closer than every data point found so far,
+/// so taking the first `k` items only visits the part of the tree around the test point.
+pub(crate) struct Nearest<'tree, T, D = Point> {
+    target: D,
+    queue: BinaryHeap<Candidate<'tree, T>>,
+}
+
+impl<'tree, T, D> Nearest<'tree, T, D>
+where
+    D: Target,
+{
+    pub(crate) fn new(root: NodeRef<'tree, T>, target: D) -> Self {
+        let mut queue = BinaryHeap::new();
+        queue.push(Candidate {
+            distance: target.lower_bound(root.bounds()),
+            item: Item::Node(root),
+        });
+
+        Self { target, queue }
+    }
+
+    /// Like [`Iterator::next`], but also returns the distance of the data to the target
+    pub(crate) fn next_with_distance(&mut self) -> Option<(f32, &'tree Spatial<T>)> {
+        self.next_where(&mut |_| true)
+    }
+
+    /// Collects the closest `k` data points matching the predicate
+    pub(crate) fn take_where(
+        mut self,
+        k: usize,
+        mut predicate: impl FnMut(&T) -> bool,
+    ) -> Vec<&'tree Spatial<T>> {
+        let mut found = Vec::new();
+        while found.len() < k {
+            match self.next_where(&mut predicate) {
+                Some((_, data)) => found.push(data),
+                None => break,
+            }
+        }
+        found
+    }
+
+    /// Like [`Nearest::next_with_distance`], but skips data not matching the predicate.
+    /// Skipped data is never queued, so the queue only grows with nodes and matching data.
+    pub(crate) fn next_where<P>(&mut self, predicate: &mut P) -> Option<(f32, &'tree Spatial<T>)>
+    where
+        P: FnMut(&T) -> bool,
+    {
+        while let Some(Candidate { distance, item }) = self.queue.pop() {
+            match item {
+                Item::Data(data) => return Some((distance, data)),
+                Item::Node(node) => {
+                    for tn in node.node().quadrants().flatten() {
+                        let candidate = match tn {
+                            TreeNode::Point(data) => {
+                                if !predicate(data.data()) {
+                                    continue;
+                                }
+                                Candidate {
+                                    distance: self.target.distance(data.position()),
+                                    item: Item::Data(data),
+                                }
+                            }
+                            TreeNode::Bucket(bucket) => {
+                                for data in bucket.items().iter().filter(|d| predicate(d.data())) {
+                                    self.queue.push(Candidate {
+                                        distance: self.target.distance(data.position()),
+                                        item: Item::Data(data),
+                                    });
+                                }
+                                continue;
+                            }
+                            TreeNode::Node(id) => {
+                                let child = node.get(*id);
+                                let distance = self.target.lower_bound(child.bounds());
+                                if distance == f32::INFINITY {
+                                    continue;
+                                }
+                                Candidate {
+                                    distance,
+                                    item: Item::Node(child),
+                                }
+                            }
+                        };
+                        self.queue.push(candidate);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl<'tree, T, D> Iterator for Nearest<'tree, T, D>
+where
+    D: Target,
+{
+    type Item = &'tree Spatial<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with_distance().map(|(_, data)| data)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{arena::Arena, rectangle::Rectangle};
+
+    #[test]
+    fn nearest_yields_data_by_distance() {
+        // Arrange
+        let mut arena: Arena<usize> = Arena::new_bounded(&Rectangle::new((0.0, 0.0), 10.0, 10.0));
+        let mut points: Vec<Point> = Vec::new();
+        for i in 0..10 {
+            for j in 0..10 {
+                points.push(Point::new(i as f32 + 0.5, j as f32 * 0.9 + 0.3));
+            }
+        }
+        for (index, point) in points.iter().enumerate() {
+            arena.insert((index, *point).into());
+        }
+        let pt = Point::new(3.3, 7.1);
+
+        // Act
+        let found: Vec<Point> = Nearest::new(arena.root(), pt)
+            .map(|data| *data.position())
+            .collect();
+
+        // Assert
+        points.sort_by(|a, b| {
+            a.squared_distance(&pt)
+                .partial_cmp(&b.squared_distance(&pt))
+                .unwrap()
+        });
+        assert_eq!(points.len(), found.len());
+        for (expected, actual) in points.iter().zip(found.iter()) {
+            assert_eq!(expected.squared_distance(&pt), actual.squared_distance(&pt));
+        }
+    }
+
+    #[test]
+    fn sectors_skip_rectangles_by_their_angular_span() {
+        // Arrange
+        let pt = Point::new(0.0, 0.0);
+        // left of the point and straddling the x axis, so it covers the sectors 1 to 4
+        let bounds = Rectangle::new((-1.0, -2.0), 0.9, 4.0);
+        let tests = [
+            ([false, true, true, true, true, false], true),
+            ([true, true, false, false, true, true], false),
+            ([false, true, true, false, true, false], false),
+            ([true; 6], true),
+        ];
+
+        for (sectors, skipped) in tests.iter() {
+            let full = Cell::new(*sectors);
+
+            // Act
+            let bound = Sectors { pt, full: &full }.lower_bound(&bounds);
+
+            // Assert
+            assert_eq!(*skipped, bound.is_infinite(), "{:?}", sectors);
+        }
+    }
+}
@@ -0,0 +1,302 @@
+use crate::{
+    node::NodeRef,
+    point::Point,
+    rectangle::{Quadrant, Rectangle},
+    spatial::Spatial,
+    TreeNode,
+};
+
+/// A direction in which to look for neighboring leaves
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Direction {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl Direction {
+    /// All directions, clockwise starting at north
+    pub const ALL: [Direction; 8] = [
+        Direction::North,
+        Direction::NorthEast,
+        Direction::East,
+        Direction::SouthEast,
+        Direction::South,
+        Direction::SouthWest,
+        Direction::West,
+        Direction::NorthWest,
+    ];
+
+    /// The four directions sharing an edge with a leaf, clockwise starting at north
+    pub const CARDINAL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    /// The horizontal and vertical step of the direction, north and east are positive
+    pub(crate) fn offset(&self) -> (i8, i8) {
+        match self {
+            Direction::North => (0, 1),
+            Direction::NorthEast => (1, 1),
+            Direction::East => (1, 0),
+            Direction::SouthEast => (1, -1),
+            Direction::South => (0, -1),
+            Direction::SouthWest => (-1, -1),
+            Direction::West => (-1, 0),
+            Direction::NorthWest => (-1, 1),
+        }
+    }
+}
+
+/// A leaf of a [`QuadTree`](crate::QuadTree), which is a quadrant of a node that holds no child node.
+/// A leaf is either empty, or holds a single data point, or a bucket of them
+/// for trees with a bucket capacity > 1.
+#[derive(Debug)]
+pub struct Leaf<'tree, T> {
+    bounds: Rectangle,
+    depth: usize,
+    items: &'tree [Spatial<T>],
+}
+
+impl<'tree, T> Leaf<'tree, T> {
+    /// Gets the bounds of the leaf
+    pub fn bounds(&self) -> &Rectangle {
+        &self.bounds
+    }
+
+    /// Gets the depth of the leaf, a quadrant of the root node is at depth 1
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Gets the data point stored in this leaf, if any.
+    /// For a bucket, this is the first data point, see [`Leaf::items`] for all of them.
+    pub fn item(&self) -> Option<(&'tree Point, &'tree T)> {
+        self.items
+            .first()
+            .map(|data| (data.position(), data.data()))
+    }
+
+    /// Gets all data points stored in this leaf
+    pub fn items(&self) -> impl Iterator<Item = (&'tree Point, &'tree T)> {
+        self.items.iter().map(|data| (data.position(), data.data()))
+    }
+
+    fn new(node: NodeRef<'tree, T>, quadrant: &Quadrant, depth: usize) -> Self {
+        let items = match node.quadrant(quadrant) {
+            Some(tn) => tn.data(),
+            None => &[],
+        };
+
+        Self {
+            bounds: node.bounds().create_quadrant(quadrant),
+            depth,
+            items,
+        }
+    }
+}
+
+impl<'tree, T> PartialEq for Leaf<'tree, T>
+where
+    T: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.bounds == other.bounds && self.depth == other.depth && self.items().eq(other.items())
+    }
+}
+
+/// The path from the root of a tree down to a single leaf.
+/// `quadrants[i]` is the quadrant of `nodes[i]` which leads to the next node, or the leaf itself.
+pub(crate) struct LeafPath<'tree, T> {
+    nodes: Vec<NodeRef<'tree, T>>,
+    quadrants: Vec<Quadrant>,
+}
+
+impl<'tree, T> LeafPath<'tree, T> {
+    /// Find the path to the leaf containing the given point
+    pub(crate) fn find(root: NodeRef<'tree, T>, pt: &Point) -> Option<Self> {
+        let mut path = Self {
+            nodes: Vec::new(),
+            quadrants: Vec::new(),
+        };
+
+        let mut node = root;
+        loop {
+            let quadrant = node.bounds().find_quadrant(pt)?;
+            path.nodes.push(node);
+            path.quadrants.push(quadrant);
+
+            match node.quadrant(&quadrant) {
+                Some(TreeNode::Node(child)) => node = node.get(*child),
+                _ => return Some(path),
+            }
+        }
+    }
+
+    /// The leaf this path leads to
+    pub(crate) fn leaf(&self) -> Leaf<'tree, T> {
+        let last = self.nodes.len() - 1;
+        Leaf::new(self.nodes[last], &self.quadrants[last], self.nodes.len())
+    }
+
+    /// Find all leaves touching the leaf of this path in the given direction.
+    ///
+    /// We climb up to the nearest common ancestor of the leaf and it's neighbor
+    /// and then descend again, following the path mirrored along the direction.
+    /// If the neighbor is subdivided more finely than our leaf,
+    /// all of it's leaves facing our leaf are returned.
+    pub(crate) fn neighbors(&self, direction: &Direction) -> Vec<Leaf<'tree, T>> {
+        let (dx, dy) = direction.offset();
+        let mut mirrored = self.quadrants.clone();
+
+        // climb up, stepping the quadrant at each level and carrying over to the parent level
+        let mut carry = (dx, dy);
+        let mut level = mirrored.len();
+        while carry != (0, 0) {
+            if level == 0 {
+                // we stepped over the bounds of the root, there is no neighbor
+                return Vec::new();
+            }
+            level -= 1;
+
+            let (x, carry_x) = step(mirrored[level].x_bit(), carry.0);
+            let (y, carry_y) = step(mirrored[level].y_bit(), carry.1);
+            mirrored[level] = Quadrant::from_bits(x, y);
+            carry = (carry_x, carry_y);
+        }
+
+        // descend again from the common ancestor
+        let mut node = self.nodes[level];
+        for (depth, quadrant) in mirrored.iter().enumerate().skip(level) {
+            match node.quadrant(quadrant) {
+                Some(TreeNode::Node(child)) => node = node.get(*child),
+                // the neighbor is as large, or larger than our leaf
+                _ => return vec![Leaf::new(node, quadrant, depth + 1)],
+            }
+        }
+
+        // the neighbor is subdivided more finely, collect all leaves facing us
+        let mut leaves = Vec::new();
+        collect_facing(node, (-dx, -dy), mirrored.len() + 1, &mut leaves);
+        leaves
+    }
+}
+
+/// Collect all leaves of the tree below the given node, in depth-first order
+pub(crate) fn leaves<T>(root: NodeRef<'_, T>) -> Vec<Leaf<'_, T>> {
+    let mut leaves = Vec::new();
+    collect_facing(root, (0, 0), 1, &mut leaves);
+    leaves
+}
+
+/// Step a single quadrant bit, returns the new bit and the carry to the parent level
+fn step(bit: u8, delta: i8) -> (u8, i8) {
+    match bit as i8 + delta {
+        -1 => (1, -1),
+        2 => (0, 1),
+        b => (b as u8, 0),
+    }
+}
+
+/// Collect all leaves of the given node on the side described by `side`,
+/// an offset of `-1` or `1` selects the left / bottom or right / top side, `0` selects both
+fn collect_facing<'tree, T>(
+    node: NodeRef<'tree, T>,
+    side: (i8, i8),
+    depth: usize,
+    leaves: &mut Vec<Leaf<'tree, T>>,
+) {
+    fn on_side(bit: u8, side: i8) -> bool {
+        match side {
+            -1 => bit == 0,
+            1 => bit == 1,
+            _ => true,
+        }
+    }
+
+    for quadrant in Quadrant::ALL.iter() {
+        if !on_side(quadrant.x_bit(), side.0) || !on_side(quadrant.y_bit(), side.1) {
+            continue;
+        }
+
+        match node.quadrant(quadrant) {
+            Some(TreeNode::Node(child)) => {
+                collect_facing(node.get(*child), side, depth + 1, leaves)
+            }
+            _ => leaves.push(Leaf::new(node, quadrant, depth)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::arena::Arena;
+
+    fn bounds_of<'tree>(leaves: &[Leaf<'tree, u8>]) -> Vec<Rectangle> {
+        leaves.iter().map(|l| *l.bounds()).collect()
+    }
+
+    #[test]
+    fn neighbors_across_parent_boundaries() {
+        // Arrange
+        let mut arena: Arena<u8> = Arena::new_bounded(&Rectangle::new((0.0, 0.0), 8.0, 8.0));
+        arena.insert((0, Point::new(3.5, 0.5)).into());
+        arena.insert((1, Point::new(3.5, 1.5)).into());
+
+        // the leaf at (3.5, 1.5) is at depth 3, right next to the root center line
+        let path = LeafPath::find(arena.root(), &Point::new(3.5, 1.5)).unwrap();
+        assert_eq!(Rectangle::new((3.0, 1.0), 1.0, 1.0), *path.leaf().bounds());
+
+        // Assert
+        assert_eq!(
+            vec![Rectangle::new((4.0, 0.0), 4.0, 4.0)],
+            bounds_of(&path.neighbors(&Direction::East))
+        );
+        assert_eq!(
+            vec![Rectangle::new((2.0, 2.0), 2.0, 2.0)],
+            bounds_of(&path.neighbors(&Direction::North))
+        );
+        assert_eq!(
+            vec![Rectangle::new((3.0, 0.0), 1.0, 1.0)],
+            bounds_of(&path.neighbors(&Direction::South))
+        );
+        assert_eq!(
+            Some((&Point::new(3.5, 0.5), &0)),
+            path.neighbors(&Direction::South)[0].item()
+        );
+    }
+
+    #[test]
+    fn neighbors_of_larger_leaf_are_all_facing_leaves() {
+        // Arrange
+        let mut arena: Arena<u8> = Arena::new_bounded(&Rectangle::new((0.0, 0.0), 8.0, 8.0));
+        arena.insert((0, Point::new(3.5, 0.5)).into());
+        arena.insert((1, Point::new(3.5, 1.5)).into());
+
+        let path = LeafPath::find(arena.root(), &Point::new(6.0, 2.0)).unwrap();
+
+        // Assert
+        assert_eq!(
+            vec![
+                Rectangle::new((3.0, 0.0), 1.0, 1.0),
+                Rectangle::new((3.0, 1.0), 1.0, 1.0),
+                Rectangle::new((2.0, 2.0), 2.0, 2.0),
+            ],
+            bounds_of(&path.neighbors(&Direction::West))
+        );
+        assert_eq!(
+            vec![Rectangle::new((0.0, 4.0), 4.0, 4.0)],
+            bounds_of(&path.neighbors(&Direction::NorthWest))
+        );
+        assert!(path.neighbors(&Direction::East).is_empty());
+        assert!(path.neighbors(&Direction::South).is_empty());
+    }
+}
@@ -1,16 +1,32 @@
 use crate::{
+    arena::Arena,
     point::Point,
+    ray::Ray,
     rectangle::{Quadrant, Rectangle, RectangleRelation},
     spatial::Spatial,
     TreeNode,
 };
+use std::ops::Deref;
 
+/// Index of a [`Node`] inside of an [`Arena`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct NodeId(pub(crate) u32);
+
+impl NodeId {
+    /// The root node, which is always present in an arena
+    pub(crate) const ROOT: NodeId = NodeId(0);
+}
+
+#[derive(Clone)]
 pub(crate) struct Node<T>
 where
     T: Sized,
 {
     quadrants: [Option<TreeNode<T>>; 4],
     bounds: Rectangle,
+
+    /// cached number of data points below this node, maintained by the [`Arena`]
+    count: usize,
 }
 
 impl<T> Node<T>
@@ -21,6 +37,7 @@ where
         Self {
             quadrants: [None, None, None, None],
             bounds: *bounds,
+            count: 0,
         }
     }
 
@@ -29,6 +46,15 @@ where
         &self.bounds
     }
 
+    /// Gets the number of data points stored below this node, including all child nodes
+    pub(crate) fn count(&self) -> usize {
+        self.count
+    }
+
+    pub(crate) fn count_mut(&mut self) -> &mut usize {
+        &mut self.count
+    }
+
     pub(crate) fn quadrants(&self) -> impl Iterator<Item = &Option<TreeNode<T>>> {
         self.quadrants.iter()
     }
@@ -39,79 +65,20 @@ where
 
     /// Gets references to all top-level data in this node, the iterator can be empty
     pub(crate) fn data(&self) -> impl Iterator<Item = &Spatial<T>> {
-        self.quadrants
-            .iter()
-            .filter_map(|q| q.as_ref())
-            .filter_map(|tn| match tn {
-                TreeNode::Node(_) => None,
-                TreeNode::Point(data) => Some(data),
-            })
-        //.collect()
+        self.quadrants.iter().flatten().flat_map(|tn| tn.data())
     }
 
-    /// Gets references to all top-level nodes contained in this node, the iterator can be empty
-    pub(crate) fn nodes(&self) -> impl Iterator<Item = &Node<T>> {
+    /// Gets the ids of all top-level nodes contained in this node, the iterator can be empty
+    pub(crate) fn node_ids(&self) -> impl Iterator<Item = NodeId> + '_ {
         self.quadrants
             .iter()
             .filter_map(|q| q.as_ref())
             .filter_map(|tn| match tn {
-                TreeNode::Point(_) => None,
-                TreeNode::Node(node) => Some(&**node),
-            })
-        //.collect()
-    }
-
-    pub(crate) fn nodes_mut(&mut self) -> impl Iterator<Item = &mut Node<T>> {
-        self.quadrants
-            .iter_mut()
-            .filter_map(|q| q.as_mut())
-            .filter_map(|tn| match tn {
-                TreeNode::Point(_) => None,
-                TreeNode::Node(node) => Some(&mut **node),
+                TreeNode::Node(id) => Some(*id),
+                _ => None,
             })
     }
 
-    /// Test if the given quadrant has room for an insertion
-    /// this is will panic if the given quadrant has a None value, this will panic
-    fn can_insert_unchecked(&self, quadrant: &Quadrant) -> bool {
-        let quadrant = self
-            .quadrant(quadrant)
-            .as_ref()
-            .expect("can_insert_unchecked can only work on Some values");
-        match &quadrant {
-            TreeNode::Point(_) => false,
-            TreeNode::Node(_) => true,
-        }
-    }
-
-    /// Try to split the specified quadrant of this node.
-    /// The only case in which this actually does something,
-    /// is if the given quadrant contains a single data point.
-    /// The data point will be replaced with a new node in which the data point is inserted.
-    fn split(&mut self, quadrant: &Quadrant) {
-        // we can't split none :/
-        if self.quadrant(quadrant).is_none() {
-            return;
-        }
-
-        // take out whatever is in the quadrant and match on it
-        match self.quadrant_mut(quadrant).take().unwrap() {
-            // If we took out spatial data, we create a new node and insert the data in it
-            // After that we replace the None in quadrant with the new node
-            TreeNode::Point(data) => {
-                let bounds = self.bounds.create_quadrant(quadrant);
-                let mut node: Node<T> = Node::new_bounded(&bounds);
-                node.insert(data);
-                self.quadrant_mut(quadrant)
-                    .replace(TreeNode::Node(Box::new(node)));
-            }
-            // If we took out a node, we just put it back in :)
-            TreeNode::Node(n) => {
-                self.quadrant_mut(quadrant).replace(TreeNode::Node(n));
-            }
-        }
-    }
-
     /// Get a reference to whatever is stored at the given quadrant
     pub(crate) fn quadrant(&self, quadrant: &Quadrant) -> &Option<TreeNode<T>> {
         match quadrant {
@@ -132,90 +99,112 @@ where
         }
     }
 
-    /// Insert a [`Spatial`] into the node.
-    ///
-    /// # Arguments
-    ///
-    /// * `data` - A [`Spatial`] holding data linked to a position in space
-    ///
-    /// # Panics
-    ///
-    /// This function will blow the stack if two Spatial at the exact same position are inserted.
-    ///
-    ///
-    pub(crate) fn insert(&mut self, data: Spatial<T>) {
-        // get Quadrant of data
-        let quadrant = self
-            .bounds
-            .find_quadrant(data.position())
-            .expect("data outside of bounds!");
-
-        // if the quadrant is still empty, we can insert the data and exit early
-        if self.quadrant(&quadrant).is_none() {
-            self.quadrant_mut(&quadrant).replace(TreeNode::Point(data));
-            return;
-        }
+    /// Returns the amount of data points in the Node, maximum of 4
+    /// mostly used for debug, can probably be deleted
+    pub(crate) fn data_count(&self) -> u8 {
+        self.data().count() as u8
+    }
 
-        // test if we can insert, or need to split
-        if !self.can_insert_unchecked(&quadrant) {
-            self.split(&quadrant);
-        }
+    pub(crate) fn is_empty(&self) -> bool {
+        self.quadrants.iter().filter_map(|q| q.as_ref()).count() == 0
+    }
+}
 
-        // finally insert the data :)
-        if let TreeNode::Node(n) = self
-            .quadrant_mut(&quadrant)
-            .as_mut()
-            .expect("Quadrant can not be empty here")
-        {
-            n.insert(data)
-        } else {
-        }
+/// A read-only reference to a [`Node`] together with the [`Arena`] it is stored in,
+/// so the ids of it's child nodes can be followed.
+pub(crate) struct NodeRef<'tree, T> {
+    arena: &'tree Arena<T>,
+    id: NodeId,
+}
+
+impl<'tree, T> Clone for NodeRef<'tree, T> {
+    fn clone(&self) -> Self {
+        *self
     }
+}
 
-    /// Tries to remove the data at the given position,
-    /// And returns ownership to it.
-    /// If no data is stored at the given position, the return value will be `None`.
-    /// TODO: If a node is empty after all it's contents are gone, it should be removed
-    pub(crate) fn remove(&mut self, pt: &Point) -> Option<T> {
-        if let Some(quadrant) = self.bounds.find_quadrant(pt) {
-            match self.quadrant_mut(&quadrant).take() {
-                None => None,
-                Some(tn) => match tn {
-                    TreeNode::Node(mut node) => {
-                        let t = node.remove(pt); // assign t as we can't access node anymore after we call replace
-                        self.quadrant_mut(&quadrant).replace(TreeNode::Node(node));
-                        t
-                    }
-                    TreeNode::Point(data) => {
-                        if data.position() == pt {
-                            Some(data.consume())
-                        } else {
-                            self.quadrant_mut(&quadrant).replace(TreeNode::Point(data));
-                            None
-                        }
-                    }
-                },
-            }
-        } else {
-            None
+impl<'tree, T> Copy for NodeRef<'tree, T> {}
+
+impl<'tree, T> Deref for NodeRef<'tree, T> {
+    type Target = Node<T>;
+
+    fn deref(&self) -> &Node<T> {
+        self.arena.get(self.id)
+    }
+}
+
+impl<'tree, T> NodeRef<'tree, T>
+where
+    T: Sized,
+{
+    pub(crate) fn new(arena: &'tree Arena<T>, id: NodeId) -> Self {
+        Self { arena, id }
+    }
+
+    /// Gets the id of the referenced node
+    pub(crate) fn id(&self) -> NodeId {
+        self.id
+    }
+
+    /// Gets a reference to the referenced node, living as long as the arena
+    pub(crate) fn node(&self) -> &'tree Node<T> {
+        self.arena.get(self.id)
+    }
+
+    /// Gets a reference to another node stored in the same arena
+    pub(crate) fn get(&self, id: NodeId) -> Self {
+        Self::new(self.arena, id)
+    }
+
+    /// Gets the bounds of the node, living as long as the arena
+    pub(crate) fn bounds(&self) -> &'tree Rectangle {
+        self.node().bounds()
+    }
+
+    /// Get a reference to whatever is stored at the given quadrant, living as long as the arena
+    pub(crate) fn quadrant(&self, quadrant: &Quadrant) -> &'tree Option<TreeNode<T>> {
+        self.node().quadrant(quadrant)
+    }
+
+    /// Gets references to all top-level data in this node, the iterator can be empty
+    pub(crate) fn data(&self) -> impl Iterator<Item = &'tree Spatial<T>> {
+        self.node().data()
+    }
+
+    /// Gets references to all top-level nodes contained in this node, the iterator can be empty
+    pub(crate) fn nodes(&self) -> impl Iterator<Item = NodeRef<'tree, T>> {
+        let this = *self;
+        self.node().node_ids().map(move |id| this.get(id))
+    }
+
+    /// Gets the child node stored at the given quadrant, if any
+    pub(crate) fn child(&self, quadrant: &Quadrant) -> Option<NodeRef<'tree, T>> {
+        match self.quadrant(quadrant) {
+            Some(TreeNode::Node(id)) => Some(self.get(*id)),
+            _ => None,
         }
     }
 
     /// Tries to find the data at the given test point.
     /// The return value will be either Some(&T), if an exact match was found, or None.
-    pub(crate) fn find(&self, pt: &Point) -> Option<&T> {
-        if let Some(quadrant) = self.bounds.find_quadrant(pt) {
+    pub(crate) fn find(&self, pt: &Point) -> Option<&'tree T> {
+        self.find_spatial(pt).map(|data| data.data())
+    }
+
+    pub(crate) fn find_spatial(&self, pt: &Point) -> Option<&'tree Spatial<T>> {
+        if let Some(quadrant) = self.bounds().find_quadrant(pt) {
             match self.quadrant(&quadrant) {
                 None => None,
                 Some(tn) => match tn {
                     TreeNode::Point(data) => {
                         if data.position() == pt {
-                            Some(data.data())
+                            Some(data)
                         } else {
                             None
                         }
                     }
-                    TreeNode::Node(n) => n.find(pt),
+                    TreeNode::Bucket(bucket) => bucket.find(pt),
+                    TreeNode::Node(id) => self.get(*id).find_spatial(pt),
                 },
             }
         } else {
@@ -226,7 +215,7 @@ where
     /// Finds all data stored in this node and it's child nodes,
     /// that is inside the given bounds.
     /// TODO: Visually tested, and seems to work in all edge cases :))
-    pub(crate) fn find_in_bounds(&self, bounds: &Rectangle) -> Option<Vec<&Spatial<T>>> {
+    pub(crate) fn find_in_bounds(&self, bounds: &Rectangle) -> Option<Vec<&'tree Spatial<T>>> {
         let mut data: Vec<&Spatial<T>> = Vec::new();
 
         // get rectangle relation
@@ -236,10 +225,15 @@ where
             RectangleRelation::Disjoint => None,
             RectangleRelation::Intersection => {
                 // first test all solo data for inclusion in query_rect
-                for pt in self.data() {
-                    match bounds.find_quadrant(pt.position()) {
-                        None => (),
-                        Some(_) => data.push(pt),
+                for tn in self.node().quadrants().flatten() {
+                    match tn {
+                        TreeNode::Point(pt) => {
+                            if bounds.find_quadrant(pt.position()).is_some() {
+                                data.push(pt)
+                            }
+                        }
+                        TreeNode::Bucket(bucket) => bucket.filter_in_bounds(bounds, &mut data),
+                        TreeNode::Node(_) => (),
                     }
                 }
                 // then let the child nodes handle their points, recursively
@@ -263,8 +257,7 @@ where
                     for quadrant in bounds
                         .corners()
                         .iter()
-                        .map(|c| self.bounds.find_quadrant(c))
-                        .filter_map(|q| q)
+                        .filter_map(|c| self.bounds().find_quadrant(c))
                     {
                         if quadrants.contains(&quadrant) {
                         } else {
@@ -279,12 +272,16 @@ where
                             Some(tn) => match tn {
                                 // if we have a single data point in the same quadrant as the query_rect corner,
                                 // just test for inclusion
-                                TreeNode::Point(pt) => match bounds.find_quadrant(pt.position()) {
-                                    Some(_) => data.push(pt),
-                                    None => (),
-                                },
+                                TreeNode::Point(pt) => {
+                                    if bounds.find_quadrant(pt.position()).is_some() {
+                                        data.push(pt)
+                                    }
+                                }
+                                TreeNode::Bucket(bucket) => {
+                                    bucket.filter_in_bounds(bounds, &mut data)
+                                }
                                 // hand responsibility over to the child node
-                                TreeNode::Node(node) => match node.find_in_bounds(bounds) {
+                                TreeNode::Node(id) => match self.get(*id).find_in_bounds(bounds) {
                                     None => (),
                                     Some(ts) => data.extend(ts),
                                 },
@@ -297,74 +294,186 @@ where
         }
     }
 
-    pub(crate) fn minimum_coordinate_distance(&self, pt: &Point) -> Option<f32> {
-        fn coordinate_distance(a: &Point, b: &Point) -> f32 {
-            (a.x() - b.x()).abs().max((a.y() - b.y()).abs())
+    /// Push all data stored in this node and it's child nodes, that is inside the given bounds, onto `found`.
+    /// The buckets of all nodes crossing the bounds are filtered either in chunks or one position at a time.
+    pub(crate) fn scan_in_bounds(
+        &self,
+        bounds: &Rectangle,
+        chunked: bool,
+        found: &mut Vec<&'tree Spatial<T>>,
+    ) {
+        if self.bounds().relation(bounds) == RectangleRelation::Disjoint {
+            return;
         }
 
-        if let Some(quadrant) = self.bounds().find_quadrant(pt) {
-            match self.quadrant(&quadrant) {
-                None => {
-                    let mut corners = self.bounds().corners();
-                    corners.sort_by(|a, b| {
-                        a.squared_distance(pt)
-                            .partial_cmp(&b.squared_distance(pt))
-                            .expect("Could not order corners")
-                    });
-                    corners.reverse();
-                    let farthest_corner = corners.first().expect("Corners can not be empty");
-
-                    Some(coordinate_distance(pt, &farthest_corner) + 0.1)
+        for tn in self.node().quadrants().flatten() {
+            match tn {
+                TreeNode::Point(pt) => {
+                    if bounds.contains(*pt.position()) {
+                        found.push(pt)
+                    }
                 }
-                Some(tn) => match tn {
-                    TreeNode::Point(cur_pt) => {
-                        Some(coordinate_distance(pt, cur_pt.position()) + 0.1)
+                TreeNode::Bucket(bucket) if chunked => bucket.filter_in_bounds(bounds, found),
+                TreeNode::Bucket(bucket) => bucket.filter_in_bounds_scalar(bounds, found),
+                TreeNode::Node(id) => self.get(*id).scan_in_bounds(bounds, chunked, found),
+            }
+        }
+    }
+
+    /// Finds all data stored in this node and it's child nodes,
+    /// that is inside the circle described by `center` and `radius`.
+    pub(crate) fn find_in_radius(
+        &self,
+        center: &Point,
+        radius: f32,
+    ) -> Option<Vec<&'tree Spatial<T>>> {
+        if self.bounds().squared_distance(center) > radius * radius {
+            return None;
+        }
+
+        let mut data: Vec<&Spatial<T>> = Vec::new();
+        self.collect_in_radius(center, radius, &mut data);
+        Some(data)
+    }
+
+    fn collect_in_radius(&self, center: &Point, radius: f32, data: &mut Vec<&'tree Spatial<T>>) {
+        for tn in self.node().quadrants().flatten() {
+            match tn {
+                TreeNode::Point(pt) => {
+                    if pt.position().squared_distance(center) <= radius * radius {
+                        data.push(pt)
                     }
-                    TreeNode::Node(node) => node.minimum_coordinate_distance(pt),
-                },
+                }
+                TreeNode::Bucket(bucket) => bucket.filter_in_radius(center, radius, data),
+                TreeNode::Node(id) => {
+                    let child = self.get(*id);
+                    if child.bounds().squared_distance(center) <= radius * radius {
+                        child.collect_in_radius(center, radius, data)
+                    }
+                }
             }
-        } else {
-            None
         }
     }
 
-    /// Returns the amount of data points in the Node, maximum of 4
-    /// mostly used for debug, can probably be deleted
-    pub(crate) fn data_count(&self) -> u8 {
-        self.data().count() as u8
+    /// Counts the data stored in this node and it's child nodes, that is inside of `bounds`.
+    /// Child nodes fully inside of `bounds` add their cached count.
+    pub(crate) fn count_in_bounds(&self, bounds: &Rectangle) -> usize {
+        match self.bounds().relation(bounds) {
+            RectangleRelation::Disjoint => 0,
+            RectangleRelation::Containment(false) => self.count(),
+            _ => self
+                .node()
+                .quadrants()
+                .flatten()
+                .map(|tn| match tn {
+                    TreeNode::Node(id) => self.get(*id).count_in_bounds(bounds),
+                    tn => tn
+                        .data()
+                        .iter()
+                        .filter(|data| bounds.find_quadrant(data.position()).is_some())
+                        .count(),
+                })
+                .sum(),
+        }
     }
 
-    /// Recursively collect references to all nodes stored in this node and all it's child nodes
-    pub(crate) fn node_children(&self) -> Vec<&Node<T>> {
-        let mut nodes: Vec<&Node<T>> = self.nodes().collect();
+    /// Counts the data stored in this node and it's child nodes,
+    /// that is inside the circle described by `center` and `radius`.
+    /// Child nodes fully inside of the circle add their cached count.
+    pub(crate) fn count_in_radius(&self, center: &Point, radius: f32) -> usize {
+        let squared_radius = radius * radius;
+        if self.bounds().squared_distance(center) > squared_radius {
+            return 0;
+        }
+        if self.bounds().max_squared_distance(center) <= squared_radius {
+            return self.count();
+        }
 
-        self.nodes()
-            .into_iter()
-            .for_each(|n| nodes.extend(n.node_children()));
+        self.node()
+            .quadrants()
+            .flatten()
+            .map(|tn| match tn {
+                TreeNode::Node(id) => self.get(*id).count_in_radius(center, radius),
+                tn => tn
+                    .data()
+                    .iter()
+                    .filter(|data| data.position().squared_distance(center) <= squared_radius)
+                    .count(),
+            })
+            .sum()
+    }
 
-        nodes
+    /// Counts the data stored in this node and it's child nodes, that is inside of `window`
+    /// and inside the circle described by `center` and `radius`.
+    /// Child nodes fully inside of the window are counted like [`NodeRef::count_in_radius`].
+    pub(crate) fn count_in_radius_within(
+        &self,
+        center: &Point,
+        radius: f32,
+        window: &Rectangle,
+    ) -> usize {
+        let squared_radius = radius * radius;
+        if self.bounds().squared_distance(center) > squared_radius {
+            return 0;
+        }
+
+        match self.bounds().relation(window) {
+            RectangleRelation::Disjoint => 0,
+            RectangleRelation::Containment(false) => self.count_in_radius(center, radius),
+            _ => self
+                .node()
+                .quadrants()
+                .flatten()
+                .map(|tn| match tn {
+                    TreeNode::Node(id) => {
+                        self.get(*id).count_in_radius_within(center, radius, window)
+                    }
+                    tn => tn
+                        .data()
+                        .iter()
+                        .filter(|data| {
+                            window.contains(*data.position())
+                                && data.position().squared_distance(center) <= squared_radius
+                        })
+                        .count(),
+                })
+                .sum(),
+        }
     }
 
-    /// Recursively collect mutable references to the nodes stored in this nodes children
-    /// WARN: The direct child nodes of this node are not returned, because of
-    /// a possibly double mutable borrow
-    pub(crate) fn node_children_mut(&mut self) -> Vec<&mut Node<T>> {
-        let mut nodes: Vec<&mut Node<T>> = Vec::new();
+    /// Finds all data stored in this node and it's child nodes that is hit by the ray,
+    /// together with it's distance along the ray, in no particular order
+    pub(crate) fn find_along(&self, ray: &Ray, data: &mut Vec<(f32, &'tree Spatial<T>)>) {
+        if !ray.crosses(self.bounds()) {
+            return;
+        }
+
+        for tn in self.node().quadrants().flatten() {
+            match tn {
+                TreeNode::Node(id) => self.get(*id).find_along(ray, data),
+                tn => data.extend(
+                    tn.data()
+                        .iter()
+                        .filter_map(|pt| ray.hit(pt.position()).map(|t| (t, pt))),
+                ),
+            }
+        }
+    }
 
-        self.nodes_mut()
-            .into_iter()
-            .for_each(|n| nodes.extend(n.node_children_mut()));
+    /// Recursively collect references to all nodes stored in this node and all it's child nodes
+    pub(crate) fn node_children(&self) -> Vec<NodeRef<'tree, T>> {
+        let mut nodes: Vec<NodeRef<'tree, T>> = self.nodes().collect();
+
+        self.nodes().for_each(|n| nodes.extend(n.node_children()));
 
         nodes
     }
 
     /// Recursively collect references to all data points stored in this node and all it's child nodes.
-    pub(crate) fn data_children(&self) -> Vec<&Spatial<T>> {
+    pub(crate) fn data_children(&self) -> Vec<&'tree Spatial<T>> {
         let mut data: Vec<&Spatial<T>> = self.data().collect();
 
-        self.nodes()
-            .into_iter()
-            .for_each(|n| data.extend(n.data_children()));
+        self.nodes().for_each(|n| data.extend(n.data_children()));
 
         data
     }
@@ -380,11 +489,11 @@ where
     }
 
     /// Visit this node and all child nodes and call a closure on the currently visited node
-    pub(crate) fn visit_nodes_ref<'tree, F>(&'tree self, f: &mut F)
+    pub(crate) fn visit_nodes_ref<F>(&self, f: &mut F)
     where
-        F: FnMut(&'tree Self),
+        F: FnMut(NodeRef<'tree, T>),
     {
-        f(&self);
+        f(*self);
 
         self.nodes().for_each(|n| n.visit_nodes_ref(f));
     }
@@ -403,60 +512,11 @@ where
     /// and call a closure on each data point
     pub(crate) fn visit_data_ref<F>(&self, f: &mut F)
     where
-        F: FnMut(&Spatial<T>),
+        F: FnMut(&'tree Spatial<T>),
     {
-        self.data().for_each(|data| f(data));
+        self.data().for_each(&mut *f);
         self.nodes().for_each(|n| n.visit_data_ref(f));
     }
-
-    pub(crate) fn is_empty(&self) -> bool {
-        self.quadrants.iter().filter_map(|q| q.as_ref()).count() == 0
-    }
-
-    /// Collapse this node recursively, returning all data points stored
-    pub(crate) fn collapse(&mut self) -> Vec<Spatial<T>> {
-        let mut data: Vec<Spatial<T>> = Vec::new();
-
-        // first all child nodes
-        self.node_children_mut().iter_mut().for_each(|n| {
-            n.quadrants_mut().for_each(|q| match q.take() {
-                None => (),
-                Some(tn) => match tn {
-                    TreeNode::Point(pt) => data.push(pt),
-                    TreeNode::Node(mut node) => data.extend(node.collapse()),
-                },
-            })
-        });
-
-        // // then we violate DRY and do it again for top-level quadrants :/
-        self.quadrants_mut().for_each(|q| match q.take() {
-            None => (),
-            Some(tn) => match tn {
-                TreeNode::Point(pt) => data.push(pt),
-                TreeNode::Node(mut node) => data.extend(node.collapse()),
-            },
-        });
-
-        data
-    }
-
-    // this only removes the child at the bottom of the stack
-    // TODO: We probably need space queries to merge nodes that contain points after removal
-    pub(crate) fn shrink_children(&mut self) {
-        self.quadrants.iter_mut().for_each(|q| match q.take() {
-            None => (),
-            Some(tn) => match tn {
-                TreeNode::Point(_) => (),
-                TreeNode::Node(mut node) => {
-                    if node.is_empty() {
-                    } else {
-                        node.shrink_children();
-                        q.replace(TreeNode::Node(node));
-                    }
-                }
-            },
-        })
-    }
 }
 
 #[cfg(test)]
@@ -466,65 +526,65 @@ mod test {
     #[test]
     fn node_can_insert() {
         // Arrange
-        let mut node: Node<u8> = Node::new_bounded(&Rectangle::new((0.0, 0.0), 10.0, 20.0));
-        assert_eq!(0, node.data_count());
+        let mut arena: Arena<u8> = Arena::new_bounded(&Rectangle::new((0.0, 0.0), 10.0, 20.0));
+        assert_eq!(0, arena.root().data_count());
 
-        node.insert((0, Point::new(0.1, 0.2)).into());
-        assert_eq!(1, node.data_count()); // We have one data point in bl corner
+        arena.insert((0, Point::new(0.1, 0.2)).into());
+        assert_eq!(1, arena.root().data_count()); // We have one data point in bl corner
 
-        node.insert((12, Point::new(0.1, 0.3)).into());
-        assert_eq!(0, node.data_count()); // bl corner is split, so we have 0 data points again
+        arena.insert((12, Point::new(0.1, 0.3)).into());
+        assert_eq!(0, arena.root().data_count()); // bl corner is split, so we have 0 data points again
 
-        node.insert((4, Point::new(6.0, 0.5)).into());
-        assert_eq!(1, node.data_count()); // br corner has a new data point, count should be 1
+        arena.insert((4, Point::new(6.0, 0.5)).into());
+        assert_eq!(1, arena.root().data_count()); // br corner has a new data point, count should be 1
 
-        node.insert((4, Point::new(6.0, 12.0)).into());
-        assert_eq!(2, node.data_count()); // tr corner has a new data point, count should be 2
+        arena.insert((4, Point::new(6.0, 12.0)).into());
+        assert_eq!(2, arena.root().data_count()); // tr corner has a new data point, count should be 2
 
-        node.insert((4, Point::new(2.0, 18.0)).into());
-        assert_eq!(3, node.data_count()); // br corner has a new data point, count should be 3
+        arena.insert((4, Point::new(2.0, 18.0)).into());
+        assert_eq!(3, arena.root().data_count()); // br corner has a new data point, count should be 3
     }
 
     #[test]
     fn node_can_remove() {
         // Arrange
-        let mut node: Node<u8> = Node::new_bounded(&Rectangle::new((0.0, 0.0), 10.0, 20.0));
-        assert_eq!(0, node.data_count());
-        assert!(node.is_empty());
+        let mut arena: Arena<u8> = Arena::new_bounded(&Rectangle::new((0.0, 0.0), 10.0, 20.0));
+        assert_eq!(0, arena.root().data_count());
+        assert!(arena.root().is_empty());
 
-        node.insert((0, Point::new(0.1, 0.2)).into());
-        assert_eq!(1, node.data_count()); // We have one data point in bl corner
+        arena.insert((0, Point::new(0.1, 0.2)).into());
+        assert_eq!(1, arena.root().data_count()); // We have one data point in bl corner
 
-        assert_eq!(Some(0), node.remove(&Point::new(0.1, 0.2)));
+        assert_eq!(Some(0), arena.remove(&Point::new(0.1, 0.2)));
 
         // insert some data points close to each other to trigger some splits
-        node.insert((0, (0.0, 0.0)).into());
-        node.insert((0, (0.0, 0.1)).into());
-        node.insert((0, (0.0, 0.2)).into());
+        arena.insert((0, (0.0, 0.0)).into());
+        arena.insert((0, (0.0, 0.1)).into());
+        arena.insert((0, (0.0, 0.2)).into());
 
-        assert_eq!(7, node.node_children().len());
+        assert_eq!(7, arena.root().node_children().len());
 
         // remove data points again
-        node.remove(&(0.0, 0.0).into());
-        node.remove(&(0.0, 0.1).into());
-        node.remove(&(0.0, 0.2).into());
+        arena.remove(&(0.0, 0.0).into());
+        arena.remove(&(0.0, 0.1).into());
+        arena.remove(&(0.0, 0.2).into());
 
         // child_node count should  not change, but now they are all empty
-        assert_eq!(7, node.node_children().len());
+        assert_eq!(7, arena.root().node_children().len());
 
         // collapse the node
-        assert_eq!(0, node.collapse().len());
+        assert_eq!(0, arena.collapse().len());
 
         // now the node should have no childs anymore
-        assert_eq!(0, node.node_children().len());
+        assert_eq!(0, arena.root().node_children().len());
     }
 
     #[test]
     fn node_can_query_by_bounds() {
         // Arrange
-        let mut node: Node<usize> = Node::new_bounded(&Rectangle::new((0.0, 0.0), 10.0, 10.0));
-        assert_eq!(0, node.data_count());
-        assert!(node.is_empty());
+        let mut arena: Arena<usize> = Arena::new_bounded(&Rectangle::new((0.0, 0.0), 10.0, 10.0));
+        assert_eq!(0, arena.root().data_count());
+        assert!(arena.root().is_empty());
 
         let mut points: Vec<Point> = Vec::with_capacity(100);
         for i in 0..10 {
@@ -534,14 +594,14 @@ mod test {
         }
 
         for (index, point) in points.iter().enumerate() {
-            node.insert((index, *point).into());
+            arena.insert((index, *point).into());
 
             println!("{:?}: {:?}", index, point);
         }
 
         let query_rect = Rectangle::new((5.0, 5.0), 5.6, 5.6);
-        println!("{:?}", node.find_in_bounds(&query_rect));
-        assert_eq!(25, node.find_in_bounds(&query_rect).unwrap().len())
+        println!("{:?}", arena.root().find_in_bounds(&query_rect));
+        assert_eq!(25, arena.root().find_in_bounds(&query_rect).unwrap().len())
     }
 
     #[test]
@@ -554,16 +614,11 @@ mod test {
             "size of Spatial<u8>: {:?}",
             std::mem::size_of::<Spatial<u8>>()
         );
-        println!(
-            "size of Box<Node<u8>>: {:?}",
-            std::mem::size_of::<Box<Node<u8>>>()
-        );
+        println!("size of NodeId: {:?}", std::mem::size_of::<NodeId>());
         println!(
             "size of TreeNode<u8>: {:?}",
             std::mem::size_of::<TreeNode<u8>>()
         );
         println!("size of Node<u8>: {:?}", std::mem::size_of::<Node<u8>>());
-
-        assert!(true)
     }
 }
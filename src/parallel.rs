@@ -0,0 +1,53 @@
+use std::thread;
+
+/// The number of threads to split parallel work across
+pub(crate) fn thread_count() -> usize {
+    thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
+
+/// Apply `f` to all items on scoped threads, returning the results in the order of the items
+pub(crate) fn map<I, R, F>(items: &[I], f: F) -> Vec<R>
+where
+    I: Sync,
+    R: Send,
+    F: Fn(&I) -> R + Sync,
+{
+    let threads = thread_count();
+    let chunk_size = ((items.len() + threads - 1) / threads).max(1);
+    if chunk_size == items.len() {
+        return items.iter().map(f).collect();
+    }
+
+    let f = &f;
+    thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(f).collect::<Vec<R>>()))
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("Parallel worker panicked"))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn map_keeps_order() {
+        // Arrange
+        let items: Vec<usize> = (0..1000).collect();
+
+        // Act
+        let doubled = map(&items, |i| i * 2);
+
+        // Assert
+        assert_eq!(items.iter().map(|i| i * 2).collect::<Vec<_>>(), doubled);
+        assert!(map(&[] as &[usize], |i| *i).is_empty());
+    }
+}
@@ -0,0 +1,364 @@
+use crate::{
+    point::Point,
+    rectangle::{Quadrant, Rectangle, RectangleRelation},
+    spatial::Spatial,
+};
+use std::sync::Arc;
+
+/// Whatever is stored in a quadrant of a [`PersistentNode`], shared between versions
+enum Entry<T> {
+    Point(Arc<Spatial<T>>),
+    Node(Arc<PersistentNode<T>>),
+}
+
+impl<T> Clone for Entry<T> {
+    fn clone(&self) -> Self {
+        match self {
+            Entry::Point(data) => Entry::Point(Arc::clone(data)),
+            Entry::Node(node) => Entry::Node(Arc::clone(node)),
+        }
+    }
+}
+
+/// A node with the same quadrant layout as the nodes of a [`QuadTree`](crate::QuadTree),
+/// which is never modified after it has been shared.
+struct PersistentNode<T> {
+    quadrants: [Option<Entry<T>>; 4],
+    bounds: Rectangle,
+}
+
+impl<T> PersistentNode<T> {
+    fn new_bounded(bounds: &Rectangle) -> Self {
+        Self {
+            quadrants: [None, None, None, None],
+            bounds: *bounds,
+        }
+    }
+
+    /// Copy this node, sharing everything stored in it's quadrants
+    fn shallow_clone(&self) -> Self {
+        Self {
+            quadrants: self.quadrants.clone(),
+            bounds: self.bounds,
+        }
+    }
+
+    fn quadrant(&self, quadrant: &Quadrant) -> &Option<Entry<T>> {
+        &self.quadrants[quadrant.index()]
+    }
+
+    fn quadrant_mut(&mut self, quadrant: &Quadrant) -> &mut Option<Entry<T>> {
+        &mut self.quadrants[quadrant.index()]
+    }
+
+    /// Create a copy of this node with the data inserted, only the nodes on the path to the data are copied
+    fn insert(&self, data: Arc<Spatial<T>>) -> Self {
+        let quadrant = self
+            .bounds
+            .find_quadrant(data.position())
+            .expect("data outside of bounds!");
+
+        let entry = match self.quadrant(&quadrant) {
+            None => Entry::Point(data),
+            Some(Entry::Node(node)) => Entry::Node(Arc::new(node.insert(data))),
+            // split the quadrant, by moving both data points into a new node
+            Some(Entry::Point(existing)) => {
+                let node = PersistentNode::new_bounded(&self.bounds.create_quadrant(&quadrant))
+                    .insert(Arc::clone(existing))
+                    .insert(data);
+                Entry::Node(Arc::new(node))
+            }
+        };
+
+        let mut copy = self.shallow_clone();
+        copy.quadrant_mut(&quadrant).replace(entry);
+        copy
+    }
+
+    /// Create a copy of this node without the data at the given position,
+    /// or `None` if no data is stored at the position.
+    /// Nodes left with a single data point are merged into their parent,
+    /// so the result has the same shape as a tree built without the removed data.
+    fn remove(&self, pt: &Point) -> Option<Self> {
+        let quadrant = self.bounds.find_quadrant(pt)?;
+
+        let entry = match self.quadrant(&quadrant) {
+            None => return None,
+            Some(Entry::Point(data)) if data.position() == pt => None,
+            Some(Entry::Point(_)) => return None,
+            Some(Entry::Node(node)) => {
+                let node = node.remove(pt)?;
+                let mut entries = node.quadrants.iter().flatten();
+                match (entries.next(), entries.next()) {
+                    (None, _) => None,
+                    (Some(Entry::Point(data)), None) => Some(Entry::Point(Arc::clone(data))),
+                    _ => Some(Entry::Node(Arc::new(node))),
+                }
+            }
+        };
+
+        let mut copy = self.shallow_clone();
+        *copy.quadrant_mut(&quadrant) = entry;
+        Some(copy)
+    }
+
+    fn find(&self, pt: &Point) -> Option<&Spatial<T>> {
+        match self.quadrant(&self.bounds.find_quadrant(pt)?) {
+            Some(Entry::Point(data)) if data.position() == pt => Some(data),
+            Some(Entry::Node(node)) => node.find(pt),
+            _ => None,
+        }
+    }
+
+    /// Collect all data stored in this node and it's child nodes, that is inside the given bounds
+    fn find_in_bounds<'tree>(&'tree self, bounds: &Rectangle, data: &mut Vec<&'tree Spatial<T>>) {
+        let contained = match self.bounds.relation(bounds) {
+            RectangleRelation::Disjoint => return,
+            RectangleRelation::Containment(false) => true,
+            _ => false,
+        };
+
+        for entry in self.quadrants.iter().flatten() {
+            match entry {
+                Entry::Point(pt) => {
+                    if contained || bounds.find_quadrant(pt.position()).is_some() {
+                        data.push(pt)
+                    }
+                }
+                Entry::Node(node) => node.find_in_bounds(bounds, data),
+            }
+        }
+    }
+}
+
+/// # PersistentQuadTree
+/// An immutable quadtree, where every modification creates a new version of the tree.
+///
+/// Versions share all subtrees that were not touched by the modification through [`Arc`],
+/// so an insertion or removal only copies the nodes on the path to the modified data point.
+/// Old versions stay valid and can be queried, sent to other threads, or restored,
+/// which makes cheap undo and consistent read snapshots possible.
+/// Cloning a version is O(1).
+///
+/// # Examples
+///
+/// ```
+/// use quadtree::{PersistentQuadTree, Rectangle};
+/// let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+/// let empty = PersistentQuadTree::new_bounded(&bounds);
+///
+/// let one = empty.insert(1u8, (2.0, 2.0)).unwrap();
+/// let two = one.insert(2, (8.0, 8.0)).unwrap();
+/// let undone = two.remove((8.0, 8.0)).unwrap();
+///
+/// assert_eq!(0, empty.len());
+/// assert_eq!(2, two.len());
+/// assert_eq!(Some(&2), two.find_exact((8.0, 8.0)));
+/// assert_eq!(None, undone.find_exact((8.0, 8.0)));
+/// assert_eq!(Some(&1), undone.find_exact((2.0, 2.0)));
+/// ```
+pub struct PersistentQuadTree<T> {
+    root: Arc<PersistentNode<T>>,
+    len: usize,
+}
+
+impl<T> Clone for PersistentQuadTree<T> {
+    fn clone(&self) -> Self {
+        Self {
+            root: Arc::clone(&self.root),
+            len: self.len,
+        }
+    }
+}
+
+impl<T> PersistentQuadTree<T> {
+    /// Creates an empty `PersistentQuadTree` with the given bounds
+    ///
+    /// # Arguments
+    ///
+    /// * `bounds` - A [`Rectangle`] by which the quadtree will be bounded
+    pub fn new_bounded(bounds: &Rectangle) -> Self {
+        Self {
+            root: Arc::new(PersistentNode::new_bounded(bounds)),
+            len: 0,
+        }
+    }
+
+    /// Gets the bounds of the tree
+    pub fn bounds(&self) -> &Rectangle {
+        &self.root.bounds
+    }
+
+    /// Creates a new version of the tree with the given data inserted, this version is not modified.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The data to be inserted
+    /// * `position` - The position of the data, or anything implementing `Into<Point>`
+    ///
+    /// # Failure
+    ///
+    /// Returns `None` if the position is outside of the bounds of the tree,
+    /// or the tree already contains a data point at the given position.
+    pub fn insert(&self, data: T, position: impl Into<Point>) -> Option<Self> {
+        let position = position.into();
+        if self.bounds().find_quadrant(&position).is_none() || self.contains(position) {
+            return None;
+        }
+
+        Some(Self {
+            root: Arc::new(self.root.insert(Arc::new((data, position).into()))),
+            len: self.len + 1,
+        })
+    }
+
+    /// Creates a new version of the tree without the data at the given position,
+    /// this version is not modified and still holds the data.
+    ///
+    /// # Arguments
+    ///
+    /// * `pt` - The [`Point`] at which to remove data, or anything implementing `Into<Point>`
+    ///
+    /// # Failure
+    ///
+    /// Returns `None` if no data is stored at the given position.
+    pub fn remove(&self, pt: impl Into<Point>) -> Option<Self> {
+        self.root.remove(&pt.into()).map(|root| Self {
+            root: Arc::new(root),
+            len: self.len - 1,
+        })
+    }
+
+    /// Test if this version of the tree contains data at the given position
+    pub fn contains(&self, pt: impl Into<Point>) -> bool {
+        self.root.find(&pt.into()).is_some()
+    }
+
+    /// Finds the data stored exactly at the given position in this version of the tree
+    pub fn find_exact(&self, pt: impl Into<Point>) -> Option<&T> {
+        self.root.find(&pt.into()).map(|data| data.data())
+    }
+
+    /// Finds the data of all data points inside of the given bounds
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{PersistentQuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+    /// let tree = PersistentQuadTree::new_bounded(&bounds)
+    ///     .insert(1u8, (2.0, 2.0))
+    ///     .and_then(|tree| tree.insert(2, (8.0, 8.0)))
+    ///     .unwrap();
+    ///
+    /// let query = Rectangle::new((1.0, 1.0), 2.0, 2.0);
+    /// assert_eq!(vec![&1], tree.find_in_bounds(&query));
+    /// ```
+    pub fn find_in_bounds(&self, bounds: &Rectangle) -> Vec<&T> {
+        self.collect_in_bounds(bounds)
+            .into_iter()
+            .map(|data| data.data())
+            .collect()
+    }
+
+    /// Finds the positions of all data points inside of the given bounds
+    pub fn find_in_bounds_positions(&self, bounds: &Rectangle) -> Vec<Point> {
+        self.collect_in_bounds(bounds)
+            .into_iter()
+            .map(|data| *data.position())
+            .collect()
+    }
+
+    fn collect_in_bounds(&self, bounds: &Rectangle) -> Vec<&Spatial<T>> {
+        let mut data = Vec::new();
+        self.root.find_in_bounds(bounds, &mut data);
+        data
+    }
+
+    /// Returns an iterator over the positions and data of all data points in this version
+    pub fn iter(&self) -> impl Iterator<Item = (&Point, &T)> {
+        self.collect_in_bounds(self.bounds())
+            .into_iter()
+            .map(|data| (data.position(), data.data()))
+    }
+
+    /// Returns the number of data points in this version of the tree
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Test if this version of the tree holds no data
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Test if both versions are the same, without comparing their contents.
+    /// A version is only the same as itself and it's clones.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.root, &other.root)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn shared(a: &Option<Entry<u8>>, b: &Option<Entry<u8>>) -> bool {
+        match (a, b) {
+            (Some(Entry::Node(a)), Some(Entry::Node(b))) => Arc::ptr_eq(a, b),
+            (Some(Entry::Point(a)), Some(Entry::Point(b))) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn persistent_versions_share_untouched_subtrees() {
+        // Arrange
+        let tree = PersistentQuadTree::new_bounded(&Rectangle::new((0.0, 0.0), 8.0, 8.0))
+            .insert(0u8, (1.0, 1.0))
+            .and_then(|tree| tree.insert(1, (1.0, 1.5)))
+            .and_then(|tree| tree.insert(2, (7.0, 7.0)))
+            .unwrap();
+
+        // Act
+        let next = tree.insert(3, (7.0, 1.0)).unwrap();
+
+        // Assert
+        let bottom_left = Quadrant::BottomLeft;
+        let top_right = Quadrant::TopRight;
+        assert!(!next.ptr_eq(&tree));
+        assert!(shared(
+            tree.root.quadrant(&bottom_left),
+            next.root.quadrant(&bottom_left)
+        ));
+        assert!(shared(
+            tree.root.quadrant(&top_right),
+            next.root.quadrant(&top_right)
+        ));
+        assert!(tree.root.quadrant(&Quadrant::BottomRight).is_none());
+        assert_eq!(3, tree.len());
+        assert_eq!(4, next.len());
+    }
+
+    #[test]
+    fn persistent_remove_merges_single_points() {
+        // Arrange
+        let tree = PersistentQuadTree::new_bounded(&Rectangle::new((0.0, 0.0), 8.0, 8.0))
+            .insert(0u8, (1.0, 1.0))
+            .and_then(|tree| tree.insert(1, (1.0, 1.5)))
+            .unwrap();
+
+        // Act
+        let removed = tree.remove((1.0, 1.5)).unwrap();
+
+        // Assert
+        assert!(tree.remove((5.0, 5.0)).is_none());
+        assert!(matches!(
+            removed.root.quadrant(&Quadrant::BottomLeft),
+            Some(Entry::Point(_))
+        ));
+        assert_eq!(1, removed.len());
+        assert_eq!(Some(&1), tree.find_exact((1.0, 1.5)));
+        assert!(removed.remove((1.0, 1.0)).unwrap().is_empty());
+    }
+}
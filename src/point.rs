@@ -23,6 +23,11 @@ impl Point {
         self.y
     }
 
+    /// Test if neither coordinate is NaN or infinite
+    pub fn is_finite(&self) -> bool {
+        self.x.is_finite() && self.y.is_finite()
+    }
+
     pub(crate) fn squared_distance(&self, other: &Point) -> f32 {
         (self.x - other.x).powi(2) + (self.y - other.y).powi(2)
     }
@@ -1,16 +1,59 @@
-use crate::{node::Node, point, point::Point, rectangle::Rectangle, spatial::Spatial};
+use crate::{
+    arena::Arena,
+    builder::QuadTreeBuilder,
+    cursor::Cursor,
+    debug::{self, DebugNode},
+    error::InsertError,
+    iter::{IntoIter, Iter},
+    join,
+    mesh::{self, Mesh},
+    metric::{Measured, Metric},
+    nearest::{self, Farthest, Nearest, Sectors, Wrapping},
+    neighbor::{Direction, Leaf, LeafPath},
+    node::NodeRef,
+    parallel,
+    point::Point,
+    ray::Ray,
+    rectangle::{Boundary, Quadrant, Rectangle, RectangleRelation},
+    spatial::Spatial,
+    stats::{self, TreeStats},
+    svg::{self, SvgOptions, SvgQuery},
+    visitor::{self, TreeVisitor},
+    TreeNode,
+};
+use std::{
+    cell::Cell,
+    fmt::{self, Debug},
+    iter::FromIterator,
+};
 
 /// # QuadTree
 /// A simple, naive implementation of a basic [QuadTree](https://en.wikipedia.org/wiki/Quadtree) data structure.
 /// Allows insertion of metadata which implements [`Sized`] with
 /// a position in 2d-Space, a [`Point`]. This was one of my first projects ever with rust, so
 /// this should definitely not be considered something production ready, but rather a learning experience.
+///
+/// A `QuadTree<T>` is [`Send`] and [`Sync`] whenever `T` is,
+/// so it can be shared between threads for concurrent reads.
+#[derive(Clone)]
 pub struct QuadTree<T>
 where
     T: Sized,
 {
-    root: Node<T>,
+    nodes: Arena<T>,
     bounds: Rectangle,
+
+    /// cached number of data points, maintained on insert and remove
+    len: usize,
+
+    /// skip chains of nodes with a single child, see [`QuadTreeBuilder::compressed`]
+    compressed: bool,
+
+    /// distance within which positions are duplicates, see [`QuadTreeBuilder::tolerance`]
+    tolerance: f32,
+
+    /// wrap queries around the edges of the bounds, see [`QuadTreeBuilder::wrapping`]
+    wrapping: bool,
 }
 
 impl<T> QuadTree<T>
@@ -21,7 +64,8 @@ where
     ///
     /// # Arguments
     ///
-    /// * `bounds` - A [`Rectangle`] by which the quadtree will be bounded
+    /// * `bounds` - A [`Rectangle`] by which the quadtree will be bounded,
+    ///   it's [`Boundary`](crate::Boundary) decides if data on the right and top edge is inside
     ///
     /// # Examples
     ///
@@ -34,11 +78,59 @@ where
     ///
     pub const fn new_bounded(bounds: &Rectangle) -> Self {
         Self {
-            root: Node::new_bounded(bounds),
+            nodes: Arena::new_bounded(bounds),
             bounds: *bounds,
+            len: 0,
+            compressed: false,
+            tolerance: 0.0,
+            wrapping: false,
         }
     }
 
+    pub(crate) fn from_builder(builder: &QuadTreeBuilder) -> Self {
+        // the right and top edge of a wrapping space are the left and bottom edge again
+        let bounds = if builder.wrapping {
+            builder.bounds.with_boundary(Boundary::HalfOpen)
+        } else {
+            builder.bounds
+        };
+        let mut quadtree = Self {
+            compressed: builder.compressed,
+            tolerance: builder.tolerance,
+            wrapping: builder.wrapping,
+            ..Self::new_bounded(&bounds)
+        };
+        quadtree.nodes.set_bucket_capacity(builder.bucket_capacity);
+        quadtree
+    }
+
+    /// Test if the tree is stored path-compressed, see [`QuadTreeBuilder::compressed`]
+    pub fn is_compressed(&self) -> bool {
+        self.compressed
+    }
+
+    /// Test if queries wrap around the edges of the bounds, see [`QuadTreeBuilder::wrapping`]
+    pub fn is_wrapping(&self) -> bool {
+        self.wrapping
+    }
+
+    /// Gets a read-only reference to the root node
+    pub(crate) fn root(&self) -> NodeRef<'_, T> {
+        self.nodes.root()
+    }
+
+    /// Gets the distance within which positions count as duplicates on insertion,
+    /// see [`QuadTreeBuilder::tolerance`]
+    pub fn tolerance(&self) -> f32 {
+        self.tolerance
+    }
+
+    /// Gets the number of data points a leaf holds before it is split,
+    /// see [`QuadTreeBuilder::bucket_capacity`]
+    pub fn bucket_capacity(&self) -> usize {
+        self.nodes.bucket_capacity()
+    }
+
     /// Tries to insert a given spatial data into the quadtree,
     ///
     /// # Arguments
@@ -50,9 +142,12 @@ where
     /// This function can fail and will return `false` if it did.
     ///
     /// Fail cases are:
+    /// * A coordinate of the position is NaN or infinite
     /// * The spatial data is outside of the bounds of the tree
     /// * The tree already contains a data point at the given position
     ///
+    /// See [`QuadTree::try_insert`] to find out why inserting failed.
+    ///
     /// # Examples
     ///
     /// ```
@@ -71,32 +166,118 @@ where
     /// ```
     ///
     pub fn insert(&mut self, data: T, position: impl Into<Point>) -> bool {
-        let data: Spatial<T> = (data, position.into()).into();
+        self.try_insert(data, position).is_ok()
+    }
+
+    /// Tries to insert data into the quadtree, returning why it failed if it did
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The data to be inserted
+    /// * `position` - The [`Point`] at which to insert, or anything implementing `Into<Point>`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{InsertError, QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// assert_eq!(Ok(()), quadtree.try_insert(12u8, (0.0, 0.5)));
+    /// assert_eq!(Err(InsertError::Duplicate), quadtree.try_insert(6u8, (0.0, 0.5)));
+    /// assert_eq!(Err(InsertError::OutOfBounds), quadtree.try_insert(2u8, (-2.0, 5.0)));
+    /// assert_eq!(Err(InsertError::NonFinite), quadtree.try_insert(2u8, (f32::NAN, 5.0)));
+    /// ```
+    ///
+    pub fn try_insert(&mut self, data: T, position: impl Into<Point>) -> Result<(), InsertError> {
+        self.try_insert_spatial((data, position.into()).into())
+    }
+
+    fn try_insert_spatial(&mut self, data: Spatial<T>) -> Result<(), InsertError> {
+        // NaN would otherwise end up in some quadrant, and never be found again
+        if !data.position().is_finite() {
+            return Err(InsertError::NonFinite);
+        }
 
         // test if data is in tree bounds
         if self.bounds.find_quadrant(data.position()).is_none() {
-            return false;
+            return Err(InsertError::OutOfBounds);
         }
 
         // test if data point is already contained, this would otherwise blow the stack
-        if self.contains(*data.position()) {
-            return false;
+        if self.contains_approx(*data.position(), self.tolerance) {
+            return Err(InsertError::Duplicate);
         }
 
         // finally insert
-        self.root.insert(data);
+        self.insert_unchecked_spatial(data);
+        Ok(())
+    }
+
+    /// Inserts data into the tree, or merges it into the closest data already stored
+    /// within the tolerance of the tree, see [`QuadTreeBuilder::tolerance`].
+    /// Without a tolerance, only data at exactly the same position is merged.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The data to be inserted
+    /// * `position` - The [`Point`] at which to insert, or anything implementing `Into<Point>`
+    /// * `merge` - Called with the data already stored and the new data, if the new data is a duplicate
+    ///
+    /// Returns `false` if the position is outside of the bounds of the tree,
+    /// `true` if the data was inserted or merged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, QuadTreeBuilder, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 8.0, 8.0);
+    /// let mut quadtree: QuadTree<u32> = QuadTreeBuilder::new(&bounds).tolerance(0.01).build();
+    ///
+    /// quadtree.insert(1, (2.0, 7.0));
+    /// assert!(quadtree.insert_or_merge(2, (2.001, 6.999), |count, new| *count += new));
+    ///
+    /// assert_eq!(1, quadtree.len());
+    /// assert_eq!(Some(&3), quadtree.find_exact((2.0, 7.0)));
+    /// ```
+    pub fn insert_or_merge<F>(&mut self, data: T, position: impl Into<Point>, merge: F) -> bool
+    where
+        F: FnOnce(&mut T, T),
+    {
+        let position = position.into();
+        if self.bounds.find_quadrant(&position).is_none() {
+            return false;
+        }
+
+        match self.find_approx_position(position, self.tolerance) {
+            Some(existing) => {
+                let stored = self
+                    .nodes
+                    .find_mut(&existing)
+                    .expect("Data was found at this position");
+                merge(stored, data);
+            }
+            None => self.insert_unchecked_spatial((data, position).into()),
+        }
         true
     }
 
     /// Inserts data into the tree without any checks.
-    /// If you know your data to be valid, this is faster then [`QuadTree::insert`]
+    /// If you know your data to be valid, this is faster then [`QuadTree::insert`].
+    /// Positions with NaN or infinite coordinates are outside of any tree,
+    /// inserting them panics like inserting any other position outside of the tree bounds.
     pub fn insert_unchecked(&mut self, data: T, position: impl Into<Point>) {
         // Assume the user knows what he is doing :/
-        self.root.insert((data, position.into()).into())
+        self.insert_unchecked_spatial((data, position.into()).into())
     }
 
     fn insert_unchecked_spatial(&mut self, spatial: Spatial<T>) {
-        self.root.insert(spatial)
+        if self.compressed {
+            self.nodes.insert_compressed(spatial);
+        } else {
+            self.nodes.insert(spatial);
+        }
+        self.len += 1;
     }
 
     /// Removes the data stored at the given Point, giving back ownership to `T`
@@ -119,7 +300,12 @@ where
     /// ```
     ///
     pub fn remove(&mut self, pt: impl Into<Point>) -> Option<T> {
-        self.root.remove(&pt.into())
+        let removed = self.nodes.remove(&pt.into());
+        if removed.is_some() {
+            self.len -= 1;
+        }
+
+        removed
     }
 
     /// Replace data in the `QuadTree` with other data
@@ -154,12 +340,149 @@ where
 
     /// Shrink the tree, to remove unused nodes left after removal operations
     /// Wow what a hack ...
+    ///
+    /// Released nodes are kept in memory and reused by later insertions.
     pub fn shrink(&mut self) {
-        for data in self.root.collapse() {
+        self.len = 0;
+        for data in self.nodes.collapse() {
+            self.insert_unchecked_spatial(data);
+        }
+    }
+
+    /// Rebuild the tree with new bounds, keeping it's configuration
+    fn rebound(&mut self, bounds: &Rectangle) {
+        let data = self.nodes.collapse();
+        let capacity = self.nodes.bucket_capacity();
+        self.nodes = Arena::new_bounded(bounds);
+        self.nodes.set_bucket_capacity(capacity);
+        self.bounds = *bounds;
+        self.len = 0;
+        for data in data {
             self.insert_unchecked_spatial(data);
         }
     }
 
+    /// Removes all data and nodes from the tree, keeping the allocated memory for reuse
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(3u8, (1.0, 1.0));
+    /// quadtree.insert(2u8, (1.0, 1.1));
+    /// quadtree.clear();
+    ///
+    /// assert!(quadtree.is_empty());
+    /// assert_eq!(1, quadtree.node_count());
+    /// assert!(!quadtree.contains((1.0, 1.0)));
+    /// ```
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.nodes.clear();
+    }
+
+    /// Keep only the data for which the closure returns `true`, removing everything else.
+    /// The tree is restructured in the same pass, so there is no need to [`QuadTree::shrink`] it afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - Called with the position and data of every data point, the data can be modified
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(3u8, (1.0, 1.0));
+    /// quadtree.insert(2u8, (1.0, 1.1));
+    /// quadtree.insert(7u8, (4.0, 9.0));
+    /// quadtree.retain(|_position, data| {
+    ///     *data += 1;
+    ///     *data % 2 == 0
+    /// });
+    ///
+    /// assert_eq!(2, quadtree.len());
+    /// assert_eq!(Some(&4), quadtree.find_exact((1.0, 1.0)));
+    /// assert_eq!(Some(&8), quadtree.find_exact((4.0, 9.0)));
+    /// assert_eq!(1, quadtree.node_count());
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&Point, &mut T) -> bool,
+    {
+        let removed = self.nodes.extract(None, self.compressed, |data| {
+            let (position, data) = data.parts_mut();
+            !f(position, data)
+        });
+        self.len -= removed.len();
+    }
+
+    /// Removes all data from the tree, returning the positions and data as an iterator.
+    /// The data is removed right away, even if the iterator is dropped before it is consumed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{Point, QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(3u8, (1.0, 1.0));
+    /// let drained: Vec<(Point, u8)> = quadtree.drain().collect();
+    ///
+    /// assert_eq!(vec![(Point::new(1.0, 1.0), 3)], drained);
+    /// assert!(quadtree.is_empty());
+    /// ```
+    pub fn drain(&mut self) -> impl Iterator<Item = (Point, T)> {
+        self.len = 0;
+        self.nodes.collapse().into_iter().map(Spatial::into_parts)
+    }
+
+    /// Removes all data inside of the given bounds, returning the positions and data as an iterator.
+    /// The data is removed right away, even if the iterator is dropped before it is consumed,
+    /// see [`QuadTree::remove_in_bounds`].
+    ///
+    /// # Arguments
+    ///
+    /// * `bounds` - The [`Rectangle`] in which to remove data
+    pub fn drain_in_bounds(&mut self, bounds: &Rectangle) -> impl Iterator<Item = (Point, T)> {
+        self.remove_in_bounds(bounds).into_iter()
+    }
+
+    /// Removes all data inside of the given bounds, returning the positions and data.
+    /// Only nodes intersecting the bounds are visited,
+    /// and restructured in the same pass, so there is no need to [`QuadTree::shrink`] the tree afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `bounds` - The [`Rectangle`] in which to remove data
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{Point, QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(3u8, (1.0, 1.0));
+    /// quadtree.insert(2u8, (1.0, 1.1));
+    /// quadtree.insert(7u8, (4.0, 9.0));
+    ///
+    /// let removed = quadtree.remove_in_bounds(&Rectangle::new((3.0, 8.0), 2.0, 2.0));
+    /// assert_eq!(vec![(Point::new(4.0, 9.0), 7)], removed);
+    /// assert_eq!(2, quadtree.len());
+    /// ```
+    pub fn remove_in_bounds(&mut self, bounds: &Rectangle) -> Vec<(Point, T)> {
+        let removed = self.nodes.extract(Some(bounds), self.compressed, |_| true);
+        self.len -= removed.len();
+        removed.into_iter().map(Spatial::into_parts).collect()
+    }
+
     /// Test if the `QuadTree` already contains data at the given position.
     ///
     /// # Arguments
@@ -178,10 +501,93 @@ where
     /// ```
     ///
     pub fn contains(&self, pt: impl Into<Point>) -> bool {
-        match self.root.find(&pt.into()) {
-            None => false,
-            Some(_) => true,
+        self.nodes.root().find(&pt.into()).is_some()
+    }
+
+    /// Test if the `QuadTree` contains data within `eps` of the given position
+    ///
+    /// # Arguments
+    ///
+    /// * `pt` - The point for which to test, or anything implementing `Into<Point>`
+    /// * `eps` - The maximum distance to the stored position
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(3u8, (2.0, 7.0));
+    /// assert!(quadtree.contains_approx((2.001, 6.999), 0.01));
+    /// assert!(!quadtree.contains_approx((2.1, 7.0), 0.01));
+    /// ```
+    ///
+    pub fn contains_approx(&self, pt: impl Into<Point>, eps: f32) -> bool {
+        self.find_approx_spatial(&pt.into(), eps).is_some()
+    }
+
+    /// Finds the data closest to the given position, if it is not further away than `eps`.
+    /// Unlike [`QuadTree::find_exact`], this finds positions that are off by rounding errors.
+    ///
+    /// # Arguments
+    ///
+    /// * `pt` - The point for which to test, or anything implementing `Into<Point>`
+    /// * `eps` - The maximum distance to the stored position
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(3u8, (2.0, 7.0));
+    /// quadtree.insert(4u8, (2.0, 7.05));
+    /// assert_eq!(Some(&3u8), quadtree.find_approx((2.001, 6.999), 0.01));
+    /// assert_eq!(Some(&4u8), quadtree.find_approx((2.001, 7.04), 0.1));
+    /// assert_eq!(None, quadtree.find_approx((2.1, 7.0), 0.01));
+    /// ```
+    ///
+    pub fn find_approx(&self, pt: impl Into<Point>, eps: f32) -> Option<&T> {
+        self.find_approx_spatial(&pt.into(), eps)
+            .map(|data| data.data())
+    }
+
+    /// Finds the stored position closest to the given position,
+    /// if it is not further away than `eps`, see [`QuadTree::find_approx`]
+    ///
+    /// # Arguments
+    ///
+    /// * `pt` - The point for which to test, or anything implementing `Into<Point>`
+    /// * `eps` - The maximum distance to the stored position
+    ///
+    pub fn find_approx_position(&self, pt: impl Into<Point>, eps: f32) -> Option<Point> {
+        self.find_approx_spatial(&pt.into(), eps)
+            .map(|data| *data.position())
+    }
+
+    /// A range query with radius `eps`, which usually only touches a single leaf
+    fn find_approx_spatial(&self, pt: &Point, eps: f32) -> Option<&Spatial<T>> {
+        if eps <= 0.0 {
+            return self.nodes.root().find_spatial(pt);
+        }
+
+        // sorted by increasing distance around the edges
+        if self.wrapping {
+            return self.find_spatial_in_radius(*pt, eps)?.into_iter().next();
         }
+
+        self.nodes
+            .root()
+            .find_in_radius(pt, eps)?
+            .into_iter()
+            .min_by(|a, b| {
+                a.position()
+                    .squared_distance(pt)
+                    .partial_cmp(&b.position().squared_distance(pt))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
     }
 
     /// Test if the `QuadTree` already contains data at the given position and returns a reference to it
@@ -203,37 +609,96 @@ where
     /// assert_eq!(None, quadtree.find_exact((2.001, 6.999)));
     /// ```
     ///
+    /// Positions are compared exactly, see [`QuadTree::find_approx`] to allow for rounding errors.
+    ///
     pub fn find_exact(&self, pt: impl Into<Point>) -> Option<&T> {
-        match self.root.find(&pt.into()) {
+        match self.nodes.root().find(&pt.into()) {
             None => None,
             Some(t) => Some(t),
         }
     }
 
-    // TODO: pricate nearest neighbour that returns an iterator over spatial<T>
-    // we can then consume this in the public nearest neighbour methods and map to either T or position
-    fn find_nearest_neighbors(
+    /// Iterate over all data by increasing distance to `pt`, around the edges if the tree is wrapping
+    fn nearest(&self, pt: Point) -> Box<dyn Iterator<Item = &Spatial<T>> + '_> {
+        if self.wrapping {
+            Box::new(Nearest::new(
+                self.nodes.root(),
+                Wrapping::new(pt, &self.bounds),
+            ))
+        } else {
+            Box::new(Nearest::new(self.nodes.root(), pt))
+        }
+    }
+
+    /// Run a query for every part of `bounds` inside of the tree, see [`Rectangle::wrap_into`].
+    /// Without wrapping, this is just the query on `bounds` itself.
+    fn query_parts<R>(
         &self,
-        pt: &Point,
-        n: usize,
-    ) -> Option<impl Iterator<Item = &Spatial<T>>> {
-        if let Some(dist) = self.root.minimum_coordinate_distance(&pt) {
-            let query_rect = Rectangle::new_centered(*pt, dist * 2.0, dist * 2.0);
-            match self.root.find_in_bounds(&query_rect) {
-                Some(mut data) => {
-                    data.sort_by(|a, b| {
-                        a.position()
-                            .squared_distance(&pt)
-                            .partial_cmp(&b.position().squared_distance(&pt))
-                            .expect("Failed to order candidates!")
-                    });
-                    Some(data.into_iter().take(n))
-                }
-                None => None,
+        bounds: &Rectangle,
+        query: impl Fn(&Rectangle) -> Option<Vec<R>>,
+    ) -> Option<Vec<R>> {
+        if !self.wrapping {
+            return query(bounds);
+        }
+
+        let mut found = None;
+        for part in bounds.wrap_into(&self.bounds) {
+            if let Some(data) = query(&part) {
+                found.get_or_insert_with(Vec::new).extend(data);
             }
+        }
+        found
+    }
+
+    /// Iterate over all data by increasing distance to `pt` under the given metric
+    fn nearest_by<'m, M: Metric + ?Sized>(
+        &self,
+        pt: Point,
+        metric: &'m M,
+    ) -> Nearest<'_, T, Measured<'m, M>> {
+        let space = if self.wrapping {
+            Some(self.bounds)
         } else {
             None
+        };
+        Nearest::new(self.nodes.root(), Measured::new(pt, metric, space))
+    }
+
+    fn find_spatial_in_radius_by<M: Metric + ?Sized>(
+        &self,
+        center: Point,
+        radius: f32,
+        metric: &M,
+    ) -> Option<Vec<&Spatial<T>>> {
+        if !self.wrapping && metric.lower_bound(&center, &self.bounds) > radius {
+            return None;
+        }
+
+        let mut nearest = self.nearest_by(center, metric);
+        let mut found = Vec::new();
+        while let Some((distance, data)) = nearest.next_with_distance() {
+            if distance > radius {
+                break;
+            }
+            found.push(data);
+        }
+        Some(found)
+    }
+
+    fn find_spatial_in_radius(&self, center: Point, radius: f32) -> Option<Vec<&Spatial<T>>> {
+        if !self.wrapping {
+            return self.nodes.root().find_in_radius(&center, radius);
+        }
+
+        let mut nearest = Nearest::new(self.nodes.root(), Wrapping::new(center, &self.bounds));
+        let mut found = Vec::new();
+        while let Some((distance, data)) = nearest.next_with_distance() {
+            if distance > radius * radius {
+                break;
+            }
+            found.push(data);
         }
+        Some(found)
     }
 
     /// Finds the data of the nearest neighbor to a given test point, inside the quadtree.
@@ -256,16 +721,7 @@ where
     ///
     /// ```
     pub fn find_nearest_neighbor(&self, pt: impl Into<Point>) -> Option<&T> {
-        let pt = pt.into();
-        if let Some(mut iter) = self.find_nearest_neighbors(&pt, 1) {
-            if let Some(spatial) = iter.next() {
-                Some(spatial.data())
-            } else {
-                None
-            }
-        } else {
-            None
-        }
+        self.nearest(pt.into()).next().map(|spatial| spatial.data())
     }
 
     /// Finds the position of the nearest neighbor to a given test point, inside the quadtree.
@@ -288,16 +744,9 @@ where
     ///
     /// ```
     pub fn find_nearest_neighbor_position(&self, pt: impl Into<Point>) -> Option<Point> {
-        let pt = pt.into();
-        if let Some(mut iter) = self.find_nearest_neighbors(&pt, 1) {
-            if let Some(spatial) = iter.next() {
-                Some(*spatial.position())
-            } else {
-                None
-            }
-        } else {
-            None
-        }
+        self.nearest(pt.into())
+            .next()
+            .map(|spatial| *spatial.position())
     }
 
     /// Finds all data contained in the given rectangle bounds
@@ -322,11 +771,8 @@ where
     ///
     /// ```
     pub fn find_in_bounds(&self, bounds: &Rectangle) -> Option<Vec<&T>> {
-        if let Some(data) = self.root.find_in_bounds(bounds) {
-            Some(data.iter().map(|d| d.data()).collect())
-        } else {
-            None
-        }
+        self.query_parts(bounds, |part| self.nodes.root().find_in_bounds(part))
+            .map(|data| data.iter().map(|d| d.data()).collect())
     }
 
     /// Finds all positions contained in the given rectangle bounds
@@ -351,124 +797,208 @@ where
     ///
     /// ```
     pub fn find_in_bounds_positions(&self, bounds: &Rectangle) -> Option<Vec<Point>> {
-        if let Some(data) = self.root.find_in_bounds(bounds) {
-            Some(data.iter().map(|d| *d.position()).collect())
-        } else {
-            None
-        }
+        self.query_parts(bounds, |part| self.nodes.root().find_in_bounds(part))
+            .map(|data| data.iter().map(|d| *d.position()).collect())
+    }
+
+    /// Finds all data contained in the given rectangle bounds, filtering the buckets of all nodes
+    /// crossing them either in chunks or one position at a time.
+    /// Only available with the `bench` feature, to benchmark the chunked bucket scans against the scalar ones.
+    #[cfg(feature = "bench")]
+    pub fn scan_in_bounds(&self, bounds: &Rectangle, chunked: bool) -> Vec<&T> {
+        self.scan_spatial_in_bounds(bounds, chunked)
+            .into_iter()
+            .map(|data| data.data())
+            .collect()
+    }
+
+    fn scan_spatial_in_bounds(&self, bounds: &Rectangle, chunked: bool) -> Vec<&Spatial<T>> {
+        self.query_parts(bounds, |part| {
+            let mut found = Vec::new();
+            self.nodes.root().scan_in_bounds(part, chunked, &mut found);
+            Some(found)
+        })
+        .unwrap_or_default()
     }
 
-    /// Calculate the number of all nodes in the tree
+    /// Finds all data contained in the circle described by `center` and `radius`
+    ///
+    /// # Arguments
+    ///
+    /// * `center` - The center of the query circle, or anything implementing `Into<Point>`
+    /// * `radius` - The radius of the query circle
     ///
     /// # Examples
+    ///
     /// ```
     /// use quadtree::{QuadTree, Rectangle};
     /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
     /// let mut quadtree = QuadTree::new_bounded(&bounds);
     ///
-    /// // node count on empty tree is 1, for the root
-    /// assert_eq!(1, quadtree.node_count());
-    ///
-    /// // insert two points close to each other, the root node has to split
     /// quadtree.insert(3u8, (2.0, 7.0));
     /// quadtree.insert(2, (2.5, 7.5));
+    /// quadtree.insert(1, (1.0, 5.5));
+    ///
+    /// assert_eq!(Some(vec![&2u8, &3]), quadtree.find_in_radius((2.0, 7.5), 0.6));
     ///
-    /// assert_eq!(4, quadtree.node_count());
     /// ```
-    pub fn node_count(&self) -> usize {
-        let mut count: usize = 0;
-        let mut add_node_count_to_x = || count += 1;
-        self.root.visit_nodes(&mut add_node_count_to_x);
-
-        count
+    pub fn find_in_radius(&self, center: impl Into<Point>, radius: f32) -> Option<Vec<&T>> {
+        self.find_spatial_in_radius(center.into(), radius)
+            .map(|data| data.iter().map(|d| d.data()).collect())
     }
 
-    /// Get references to the bounds of all nodes in this tree
-    /// The bounds are stored as **a**xis **a**ligned **b**ounding **b**oxes.
+    /// Finds all positions contained in the circle described by `center` and `radius`
+    ///
+    /// # Arguments
+    ///
+    /// * `center` - The center of the query circle, or anything implementing `Into<Point>`
+    /// * `radius` - The radius of the query circle
     ///
     /// # Examples
+    ///
     /// ```
     /// use quadtree::{QuadTree, Rectangle};
     /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
     /// let mut quadtree = QuadTree::new_bounded(&bounds);
     ///
-    /// // insert two points close to each other, the root node has to split
     /// quadtree.insert(3u8, (2.0, 7.0));
-    /// quadtree.insert(2, (2.5, 7.5));
+    /// quadtree.insert(1, (1.0, 5.5));
+    ///
+    /// assert_eq!(Some(vec![(2.0, 7.0).into()]), quadtree.find_in_radius_positions((2.0, 7.5), 0.6));
     ///
-    /// let bounds = [
-    ///     Rectangle::new((0.0, 0.0), 5.0, 10.0), // root bounds
-    ///     Rectangle::new((0.0, 5.0), 2.5, 5.0), // root -> top left
-    ///     Rectangle::new((1.25, 5.0), 1.25, 2.5),  // root -> top left -> top left
-    ///     Rectangle::new((1.875, 6.25), 0.625, 1.25), // root -> top left -> top left -> top right
-    /// ];
-    /// assert_eq!(bounds.iter().map(|b| b).collect::<Vec<&Rectangle>>(), quadtree.aabbs());
     /// ```
+    pub fn find_in_radius_positions(
+        &self,
+        center: impl Into<Point>,
+        radius: f32,
+    ) -> Option<Vec<Point>> {
+        self.find_spatial_in_radius(center.into(), radius)
+            .map(|data| data.iter().map(|d| *d.position()).collect())
+    }
+
+    /// Counts the data contained in the given rectangle bounds, without collecting it.
+    /// Nodes fully inside of the bounds add their cached number of data points,
+    /// instead of being walked.
     ///
+    /// # Arguments
     ///
-    pub fn aabbs<'tree>(&'tree self) -> Vec<&'tree Rectangle> {
-        let mut aabbs: Vec<&Rectangle> = Vec::new();
-        let mut push_node_bounds_onto_x = |node: &'tree Node<T>| aabbs.push(node.bounds());
-        self.root.visit_nodes_ref(&mut push_node_bounds_onto_x);
+    /// * `bounds` - The bounds inside of which data should be counted
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(3u8, (2.0, 7.0));
+    /// quadtree.insert(2, (2.5, 7.5));
+    /// quadtree.insert(1, (1.0, 5.5));
+    ///
+    /// assert_eq!(2, quadtree.count_in_bounds(&Rectangle::new((2.0, 6.0), 4.0, 5.0)));
+    /// assert_eq!(0, quadtree.count_in_bounds(&Rectangle::new((20.0, 6.0), 4.0, 5.0)));
+    /// ```
+    ///
+    pub fn count_in_bounds(&self, bounds: &Rectangle) -> usize {
+        if !self.wrapping {
+            return self.nodes.root().count_in_bounds(bounds);
+        }
 
-        aabbs
+        bounds
+            .wrap_into(&self.bounds)
+            .iter()
+            .map(|part| self.nodes.root().count_in_bounds(part))
+            .sum()
     }
 
-    /// Get the number of all data points *(or leaf nodes)* stored in this tree
+    /// Counts the data contained in the circle described by `center` and `radius`, without collecting it.
+    /// Nodes fully inside of the circle add their cached number of data points,
+    /// instead of being walked.
+    ///
+    /// # Arguments
+    ///
+    /// * `center` - The center of the query circle, or anything implementing `Into<Point>`
+    /// * `radius` - The radius of the query circle
     ///
     /// # Examples
+    ///
     /// ```
     /// use quadtree::{QuadTree, Rectangle};
     /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
     /// let mut quadtree = QuadTree::new_bounded(&bounds);
     ///
-    /// // len of empty tree is 0
-    /// assert_eq!(0, quadtree.len());
-    ///
     /// quadtree.insert(3u8, (2.0, 7.0));
-    /// assert_eq!(1, quadtree.len());
-    ///
     /// quadtree.insert(2, (2.5, 7.5));
-    /// assert_eq!(2, quadtree.len());
+    /// quadtree.insert(1, (1.0, 5.5));
     ///
-    /// quadtree.remove((2.5, 7.5));
-    /// assert_eq!(1, quadtree.len());
+    /// assert_eq!(2, quadtree.count_in_radius((2.0, 7.5), 0.6));
+    /// assert_eq!(3, quadtree.count_in_radius((2.0, 7.5), 20.0));
     /// ```
     ///
-    pub fn len(&self) -> usize {
-        let mut len: usize = 0;
-        let mut add_data_count_to_x = |node: &Node<T>| len += node.data().count();
-        self.root.visit_nodes_ref(&mut add_data_count_to_x);
+    pub fn count_in_radius(&self, center: impl Into<Point>, radius: f32) -> usize {
+        let center = center.into();
+        if !self.wrapping {
+            return self.nodes.root().count_in_radius(&center, radius);
+        }
 
-        len
+        // every position has exactly one image in a window of the size of the tree around the center,
+        // so the circle around each of the nine images of the center is counted inside of it's window only
+        let center = self.bounds.wrap_point(&center);
+        let (width, height) = (self.bounds.width(), self.bounds.height());
+        let mut count = 0;
+        for shift_x in [-width, 0.0, width].iter() {
+            for shift_y in [-height, 0.0, height].iter() {
+                let image = Point::new(center.x() + shift_x, center.y() + shift_y);
+                let window =
+                    Rectangle::new_centered(image, width, height).with_boundary(Boundary::HalfOpen);
+                count += self
+                    .nodes
+                    .root()
+                    .count_in_radius_within(&image, radius, &window);
+            }
+        }
+        count
     }
 
-    /// Returns an iterator over the positions of all data points in this tree
+    /// Finds the data of the `k` nearest neighbors to a given test point, closest first.
+    /// Returns less than `k` results, if the tree holds less than `k` data points.
+    ///
+    /// # Arguments
+    ///
+    /// * `pt` - The point for which to test, or anything implementing `Into<Point>`
+    /// * `k` - The maximum number of neighbors to find
     ///
     /// # Examples
+    ///
     /// ```
-    /// use quadtree::{QuadTree, Rectangle, Point};
+    /// use quadtree::{QuadTree, Rectangle};
     /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
     /// let mut quadtree = QuadTree::new_bounded(&bounds);
     ///
     /// quadtree.insert(3u8, (2.0, 7.0));
     /// quadtree.insert(2, (2.5, 7.5));
+    /// quadtree.insert(1, (4.0, 1.0));
     ///
-    /// let mut positions = quadtree.iter_positions();
-    /// assert_eq!(&Point::new(2.5, 7.5), positions.next().unwrap());
-    /// assert_eq!(&Point::new(2.0, 7.0), positions.next().unwrap());
-    /// assert!(positions.next().is_none());
+    /// assert_eq!(vec![&2u8, &3], quadtree.k_nearest((3.0, 8.0), 2));
+    /// assert_eq!(3, quadtree.k_nearest((3.0, 8.0), 5).len());
     /// ```
-    pub fn iter_positions(&self) -> impl Iterator<Item = &Point> {
-        self.root
-            .data_children()
-            .into_iter()
-            .map(|spatial| spatial.position())
+    ///
+    pub fn k_nearest(&self, pt: impl Into<Point>, k: usize) -> Vec<&T> {
+        self.nearest(pt.into())
+            .take(k)
+            .map(|data| data.data())
+            .collect()
     }
 
-    /// Returns an iterator over the data of all data points in this tree
+    /// Finds the positions of the `k` nearest neighbors to a given test point, closest first
+    ///
+    /// # Arguments
+    ///
+    /// * `pt` - The point for which to test, or anything implementing `Into<Point>`
+    /// * `k` - The maximum number of neighbors to find
     ///
     /// # Examples
+    ///
     /// ```
     /// use quadtree::{QuadTree, Rectangle, Point};
     /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
@@ -477,41 +1007,2479 @@ where
     /// quadtree.insert(3u8, (2.0, 7.0));
     /// quadtree.insert(2, (2.5, 7.5));
     ///
-    /// let mut positions = quadtree.iter_data();
-    /// assert_eq!(&2, positions.next().unwrap());
-    /// assert_eq!(&3, positions.next().unwrap());
-    /// assert!(positions.next().is_none());
+    /// assert_eq!(vec![Point::new(2.0, 7.0)], quadtree.k_nearest_positions((1.0, 6.0), 1));
     /// ```
-    pub fn iter_data(&self) -> impl Iterator<Item = &T> {
-        self.root
-            .data_children()
-            .into_iter()
-            .map(|spatial| spatial.data())
+    ///
+    pub fn k_nearest_positions(&self, pt: impl Into<Point>, k: usize) -> Vec<Point> {
+        self.nearest(pt.into())
+            .take(k)
+            .map(|data| *data.position())
+            .collect()
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::*;
+    /// Finds all data within `radius` of `center`, measured by the given metric
+    ///
+    /// # Arguments
+    ///
+    /// * `center` - The center of the query, or anything implementing `Into<Point>`
+    /// * `radius` - The largest distance to `center`
+    /// * `metric` - The [`Metric`] measuring distances
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{Manhattan, QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(3u8, (2.0, 7.0));
+    /// quadtree.insert(2, (2.5, 7.5));
+    /// quadtree.insert(1, (1.0, 5.5));
+    ///
+    /// assert_eq!(Some(vec![&3u8]), quadtree.find_in_radius_by((2.0, 7.2), 0.6, &Manhattan));
+    /// ```
+    ///
+    pub fn find_in_radius_by<M: Metric + ?Sized>(
+        &self,
+        center: impl Into<Point>,
+        radius: f32,
+        metric: &M,
+    ) -> Option<Vec<&T>> {
+        self.find_spatial_in_radius_by(center.into(), radius, metric)
+            .map(|data| data.iter().map(|d| d.data()).collect())
+    }
 
-    #[test]
-    fn quadtree_can_insert() {
-        // Arrange
-        let bounds: Rectangle = Rectangle::new((0.0, 0.0), 10.0, 20.0);
-        let mut quadtree: QuadTree<u8> = QuadTree::new_bounded(&bounds);
+    /// Finds all positions within `radius` of `center`, measured by the given metric
+    ///
+    /// # Arguments
+    ///
+    /// * `center` - The center of the query, or anything implementing `Into<Point>`
+    /// * `radius` - The largest distance to `center`
+    /// * `metric` - The [`Metric`] measuring distances
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{Chebyshev, QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(3u8, (2.5, 8.0));
+    /// quadtree.insert(1, (1.0, 5.5));
+    ///
+    /// assert_eq!(Some(vec![(2.5, 8.0).into()]), quadtree.find_in_radius_positions_by((2.0, 7.5), 0.5, &Chebyshev));
+    /// ```
+    ///
+    pub fn find_in_radius_positions_by<M: Metric + ?Sized>(
+        &self,
+        center: impl Into<Point>,
+        radius: f32,
+        metric: &M,
+    ) -> Option<Vec<Point>> {
+        self.find_spatial_in_radius_by(center.into(), radius, metric)
+            .map(|data| data.iter().map(|d| *d.position()).collect())
+    }
 
-        // insert a data point unchecked
-        quadtree.insert_unchecked(4, (0.0, 0.5));
+    /// Finds the data of the `k` nearest neighbors to a given test point under the given metric, closest first
+    ///
+    /// # Arguments
+    ///
+    /// * `pt` - The point for which to test, or anything implementing `Into<Point>`
+    /// * `k` - The maximum number of neighbors to find
+    /// * `metric` - The [`Metric`] measuring distances
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{Manhattan, QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(1u8, (3.0, 3.0));
+    /// quadtree.insert(2, (4.5, 0.0));
+    ///
+    /// // euclidean, the diagonal neighbor is closer
+    /// assert_eq!(vec![&1u8], quadtree.k_nearest((0.0, 0.0), 1));
+    /// assert_eq!(vec![&2u8], quadtree.k_nearest_by((0.0, 0.0), 1, &Manhattan));
+    /// ```
+    ///
+    pub fn k_nearest_by<M: Metric + ?Sized>(
+        &self,
+        pt: impl Into<Point>,
+        k: usize,
+        metric: &M,
+    ) -> Vec<&T> {
+        self.nearest_by(pt.into(), metric)
+            .take(k)
+            .map(|data| data.data())
+            .collect()
+    }
 
-        // assert it's where we assume it to be
-        assert!(quadtree.contains((0.0, 0.5)));
-        assert_eq!(Some(&4u8), quadtree.find_exact((0.0, 0.5)));
+    /// Finds the positions of the `k` nearest neighbors to a given test point under the given metric, closest first
+    ///
+    /// # Arguments
+    ///
+    /// * `pt` - The point for which to test, or anything implementing `Into<Point>`
+    /// * `k` - The maximum number of neighbors to find
+    /// * `metric` - The [`Metric`] measuring distances
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{Point, QuadTree, Rectangle, Weighted};
+    /// let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(1u8, (5.0, 7.0));
+    /// quadtree.insert(2, (8.0, 5.0));
+    ///
+    /// // vertical distances are four times as expensive
+    /// let metric = Weighted::new(1.0, 4.0);
+    /// assert_eq!(vec![Point::new(8.0, 5.0)], quadtree.k_nearest_positions_by((5.0, 5.0), 1, &metric));
+    /// ```
+    ///
+    pub fn k_nearest_positions_by<M: Metric + ?Sized>(
+        &self,
+        pt: impl Into<Point>,
+        k: usize,
+        metric: &M,
+    ) -> Vec<Point> {
+        self.nearest_by(pt.into(), metric)
+            .take(k)
+            .map(|data| *data.position())
+            .collect()
+    }
 
-        quadtree.insert(2, (1.0, 1.0));
-        quadtree.insert(2, (2.0, 1.0));
-        quadtree.insert(2, (0.5, 1.0));
+    /// Finds the data of the nearest neighbor to a given test point, which matches the predicate.
+    /// Data not matching the predicate is skipped during the search,
+    /// so only the part of the tree closer than the result is visited.
+    ///
+    /// # Arguments
+    ///
+    /// * `pt` - The point for which to test, or anything implementing `Into<Point>`
+    /// * `predicate` - Decides if the data may be returned
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// // (store number, is open)
+    /// quadtree.insert((1u8, false), (2.0, 2.0));
+    /// quadtree.insert((2, true), (6.0, 6.0));
+    /// quadtree.insert((3, true), (9.0, 9.0));
+    ///
+    /// assert_eq!(Some(&(2, true)), quadtree.nearest_where((1.0, 1.0), |(_, open)| *open));
+    /// assert_eq!(None, quadtree.nearest_where((1.0, 1.0), |(store, _)| *store > 3));
+    /// ```
+    ///
+    pub fn nearest_where(
+        &self,
+        pt: impl Into<Point>,
+        predicate: impl FnMut(&T) -> bool,
+    ) -> Option<&T> {
+        self.k_nearest_where(pt, 1, predicate).pop()
+    }
 
-        assert_eq!(5, quadtree.node_count());
-        assert_eq!(4, quadtree.len());
+    /// Finds the data of the `k` nearest neighbors to a given test point matching the predicate, closest first.
+    /// Data not matching the predicate is skipped during the search.
+    ///
+    /// # Arguments
+    ///
+    /// * `pt` - The point for which to test, or anything implementing `Into<Point>`
+    /// * `k` - The maximum number of neighbors to find
+    /// * `predicate` - Decides if the data may be returned
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(1u8, (2.0, 2.0));
+    /// quadtree.insert(2, (3.0, 3.0));
+    /// quadtree.insert(3, (4.0, 4.0));
+    /// quadtree.insert(4, (5.0, 5.0));
+    ///
+    /// assert_eq!(vec![&1, &3], quadtree.k_nearest_where((0.0, 0.0), 2, |data| data % 2 == 1));
+    /// ```
+    ///
+    pub fn k_nearest_where(
+        &self,
+        pt: impl Into<Point>,
+        k: usize,
+        predicate: impl FnMut(&T) -> bool,
+    ) -> Vec<&T> {
+        let pt = pt.into();
+        let found = if self.wrapping {
+            Nearest::new(self.nodes.root(), Wrapping::new(pt, &self.bounds))
+                .take_where(k, predicate)
+        } else {
+            Nearest::new(self.nodes.root(), pt).take_where(k, predicate)
+        };
+        found.into_iter().map(|data| data.data()).collect()
+    }
+
+    /// Finds the data farthest away from a given test point.
+    /// Nodes are pruned by the largest distance any of their positions could have,
+    /// distances are measured without wrapping around the edges.
+    ///
+    /// # Arguments
+    ///
+    /// * `pt` - The point for which to test, or anything implementing `Into<Point>`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(1u8, (2.0, 2.0));
+    /// quadtree.insert(2, (6.0, 6.0));
+    /// quadtree.insert(3, (9.0, 1.0));
+    ///
+    /// assert_eq!(Some(&3), quadtree.farthest((1.0, 1.0)));
+    /// assert_eq!(Some(&1), quadtree.farthest((9.0, 9.0)));
+    /// ```
+    ///
+    pub fn farthest(&self, pt: impl Into<Point>) -> Option<&T> {
+        Nearest::new(self.nodes.root(), Farthest(pt.into()))
+            .next()
+            .map(|data| data.data())
+    }
+
+    /// Finds the position farthest away from a given test point, see [`QuadTree::farthest`]
+    ///
+    /// # Arguments
+    ///
+    /// * `pt` - The point for which to test, or anything implementing `Into<Point>`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(1u8, (2.0, 2.0));
+    /// quadtree.insert(2, (6.0, 6.0));
+    ///
+    /// assert_eq!(Some((6.0, 6.0).into()), quadtree.farthest_position((1.0, 1.0)));
+    /// ```
+    ///
+    pub fn farthest_position(&self, pt: impl Into<Point>) -> Option<Point> {
+        Nearest::new(self.nodes.root(), Farthest(pt.into()))
+            .next()
+            .map(|data| *data.position())
+    }
+
+    /// Finds the data of all positions, which have the given test point as their nearest neighbor
+    /// among the data in the tree, see [`QuadTree::reverse_k_nearest`].
+    ///
+    /// # Arguments
+    ///
+    /// * `pt` - The point for which to test, or anything implementing `Into<Point>`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(1u8, (2.0, 5.0));
+    /// quadtree.insert(2, (1.0, 5.0));
+    /// quadtree.insert(3, (8.5, 5.0));
+    ///
+    /// // 1 and 2 are closer to each other than to the test point
+    /// assert_eq!(vec![&3], quadtree.reverse_nearest((5.0, 5.0)));
+    /// ```
+    ///
+    pub fn reverse_nearest(&self, pt: impl Into<Point>) -> Vec<&T> {
+        self.reverse_k_nearest(pt, 1)
+    }
+
+    /// Finds the data of all positions, which would have the given test point among their `k` nearest neighbors,
+    /// ordered by increasing distance to the test point.
+    /// Other data exactly as close as the test point counts as closer,
+    /// distances are measured without wrapping around the edges.
+    ///
+    /// Only the `k` closest positions in each 60° sector around the test point can qualify,
+    /// so the search stops expanding nodes in directions where they have been found.
+    ///
+    /// # Arguments
+    ///
+    /// * `pt` - The point for which to test, or anything implementing `Into<Point>`
+    /// * `k` - The number of nearest neighbors the test point has to be among
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(1u8, (2.0, 5.0));
+    /// quadtree.insert(2, (1.0, 5.0));
+    /// quadtree.insert(3, (8.5, 5.0));
+    ///
+    /// assert_eq!(vec![&1, &3, &2], quadtree.reverse_k_nearest((5.0, 5.0), 2));
+    /// ```
+    ///
+    pub fn reverse_k_nearest(&self, pt: impl Into<Point>, k: usize) -> Vec<&T> {
+        let pt = pt.into();
+        if k == 0 {
+            return Vec::new();
+        }
+
+        // the k closest data in a sector are at least as close to everything behind them as the test point
+        let full = Cell::new([false; 6]);
+        let mut counts = [0; 6];
+        let mut candidates = Vec::new();
+        for data in Nearest::new(self.nodes.root(), Sectors { pt, full: &full }) {
+            let sector = nearest::sector(&pt, data.position());
+            if counts[sector] < k {
+                candidates.push(data);
+                counts[sector] += 1;
+                if counts[sector] == k {
+                    let mut sectors = full.get();
+                    sectors[sector] = true;
+                    full.set(sectors);
+                }
+            }
+        }
+
+        candidates
+            .into_iter()
+            .filter(|candidate| {
+                let distance = candidate.position().squared_distance(&pt);
+                Nearest::new(self.nodes.root(), *candidate.position())
+                    .filter(|other| !std::ptr::eq(*other, *candidate))
+                    .take(k)
+                    .filter(|other| {
+                        other.position().squared_distance(candidate.position()) <= distance
+                    })
+                    .count()
+                    < k
+            })
+            .map(|data| data.data())
+            .collect()
+    }
+
+    /// All data hit by the ray, ordered by the distance along it
+    fn find_along(&self, ray: Option<Ray>) -> Vec<&Spatial<T>> {
+        let mut found = Vec::new();
+        if let Some(ray) = ray {
+            self.nodes.root().find_along(&ray, &mut found);
+        }
+        found.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        found.into_iter().map(|(_, data)| data).collect()
+    }
+
+    /// Finds the data hit by a ray, ordered by the distance along the ray.
+    /// A position is hit, if it is at most `radius` away from the ray.
+    /// Nodes are only searched if the ray crosses their bounds, grown by the radius.
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - The start of the ray, or anything implementing `Into<Point>`
+    /// * `direction` - The direction of the ray, of any length but zero
+    /// * `max_t` - The length of the ray
+    /// * `radius` - The largest distance of a hit position to the ray
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(1u8, (6.0, 6.2));
+    /// quadtree.insert(2, (2.0, 1.8));
+    /// quadtree.insert(3, (2.0, 8.0));
+    /// quadtree.insert(4, (9.0, 9.0));
+    ///
+    /// assert_eq!(vec![&2, &1], quadtree.raycast((0.0, 0.0), (1.0, 1.0), 10.0, 0.5));
+    /// assert!(quadtree.raycast((0.0, 0.0), (0.0, 0.0), 10.0, 0.5).is_empty());
+    /// ```
+    ///
+    pub fn raycast(
+        &self,
+        origin: impl Into<Point>,
+        direction: impl Into<Point>,
+        max_t: f32,
+        radius: f32,
+    ) -> Vec<&T> {
+        self.find_along(Ray::new(origin.into(), direction.into(), max_t, radius))
+            .into_iter()
+            .map(|data| data.data())
+            .collect()
+    }
+
+    /// Finds the data inside of a corridor around the line segment from `a` to `b`,
+    /// ordered by the distance from `a` along the segment.
+    ///
+    /// # Arguments
+    ///
+    /// * `a` - The start of the segment, or anything implementing `Into<Point>`
+    /// * `b` - The end of the segment, or anything implementing `Into<Point>`
+    /// * `width` - The largest distance to the segment, half of the corridor width
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(1u8, (7.0, 5.5));
+    /// quadtree.insert(2, (3.0, 4.5));
+    /// quadtree.insert(3, (3.0, 7.0));
+    /// quadtree.insert(4, (8.5, 5.0));
+    ///
+    /// assert_eq!(vec![&2, &1, &4], quadtree.find_along_segment((1.0, 5.0), (8.0, 5.0), 1.0));
+    /// ```
+    ///
+    pub fn find_along_segment(
+        &self,
+        a: impl Into<Point>,
+        b: impl Into<Point>,
+        width: f32,
+    ) -> Vec<&T> {
+        self.find_along(Ray::between(a.into(), b.into(), width))
+            .into_iter()
+            .map(|data| data.data())
+            .collect()
+    }
+
+    /// Finds all pairs of data in this tree and data in the other tree, whose positions are at most `distance` apart.
+    /// Both trees are walked down together, skipping pairs of nodes whose bounds are too far apart.
+    /// The pairs are in no particular order.
+    ///
+    /// Joins measure distances without wrapping around the edges, even if one or both of the trees are wrapping,
+    /// as the trees may have different bounds and there is no single space to wrap around.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The tree to join with, it may have different bounds and data
+    /// * `distance` - The largest distance between the positions of a pair
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+    /// let mut customers = QuadTree::new_bounded(&bounds);
+    /// let mut depots = QuadTree::new_bounded(&bounds);
+    ///
+    /// customers.insert("alice", (1.0, 1.0));
+    /// customers.insert("bob", (8.0, 8.0));
+    /// depots.insert(1u8, (2.0, 1.0));
+    /// depots.insert(2, (1.5, 1.5));
+    /// depots.insert(3, (5.0, 5.0));
+    ///
+    /// let mut pairs = customers.join_within(&depots, 1.0);
+    /// pairs.sort();
+    /// assert_eq!(vec![(&"alice", &1), (&"alice", &2)], pairs);
+    /// ```
+    ///
+    pub fn join_within<'a, U>(
+        &'a self,
+        other: &'a QuadTree<U>,
+        distance: f32,
+    ) -> Vec<(&'a T, &'a U)> {
+        let mut pairs = Vec::new();
+        join::join_within(self.nodes.root(), other.root(), distance, &mut pairs);
+        pairs
+            .into_iter()
+            .map(|(data, other)| (data.data(), other.data()))
+            .collect()
+    }
+
+    /// Pairs the data of every position in this tree with the data of it's nearest neighbor in the other tree,
+    /// measuring distances like [`QuadTree::join_within`]. The pairs are in no particular order,
+    /// and there are none if the other tree is empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The tree to find nearest neighbors in, it may have different bounds and data
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+    /// let mut customers = QuadTree::new_bounded(&bounds);
+    /// let mut depots = QuadTree::new_bounded(&bounds);
+    ///
+    /// customers.insert("alice", (1.0, 1.0));
+    /// customers.insert("bob", (8.0, 8.0));
+    /// depots.insert(1u8, (2.0, 1.0));
+    /// depots.insert(3, (5.0, 5.0));
+    ///
+    /// let mut pairs = customers.join_nearest(&depots);
+    /// pairs.sort();
+    /// assert_eq!(vec![(&"alice", &1), (&"bob", &3)], pairs);
+    /// ```
+    ///
+    pub fn join_nearest<'a, U>(&'a self, other: &'a QuadTree<U>) -> Vec<(&'a T, &'a U)> {
+        self.iter()
+            .filter_map(|(position, data)| {
+                Nearest::new(other.root(), *position)
+                    .next()
+                    .map(|nearest| (data, nearest.data()))
+            })
+            .collect()
+    }
+
+    /// Get the number of all nodes in the tree.
+    /// The count is cached, so this is O(1).
+    ///
+    /// # Examples
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// // node count on empty tree is 1, for the root
+    /// assert_eq!(1, quadtree.node_count());
+    ///
+    /// // insert two points close to each other, the root node has to split
+    /// quadtree.insert(3u8, (2.0, 7.0));
+    /// quadtree.insert(2, (2.5, 7.5));
+    ///
+    /// assert_eq!(4, quadtree.node_count());
+    /// ```
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Get references to the bounds of all nodes in this tree
+    /// The bounds are stored as **a**xis **a**ligned **b**ounding **b**oxes.
+    ///
+    /// # Examples
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// // insert two points close to each other, the root node has to split
+    /// quadtree.insert(3u8, (2.0, 7.0));
+    /// quadtree.insert(2, (2.5, 7.5));
+    ///
+    /// let bounds = [
+    ///     Rectangle::new((0.0, 0.0), 5.0, 10.0), // root bounds
+    ///     Rectangle::new((0.0, 5.0), 2.5, 5.0), // root -> top left
+    ///     Rectangle::new((1.25, 5.0), 1.25, 2.5),  // root -> top left -> top left
+    ///     Rectangle::new((1.875, 6.25), 0.625, 1.25), // root -> top left -> top left -> top right
+    /// ];
+    /// assert_eq!(bounds.iter().map(|b| b).collect::<Vec<&Rectangle>>(), quadtree.aabbs());
+    /// ```
+    ///
+    ///
+    pub fn aabbs<'tree>(&'tree self) -> Vec<&'tree Rectangle> {
+        let mut aabbs: Vec<&Rectangle> = Vec::new();
+        let mut push_node_bounds_onto_x = |node: NodeRef<'tree, T>| aabbs.push(node.bounds());
+        self.nodes
+            .root()
+            .visit_nodes_ref(&mut push_node_bounds_onto_x);
+
+        aabbs
+    }
+
+    /// Draw the tree as a svg document, showing the bounds of all nodes and all stored points.
+    /// If [`SvgOptions::query`] is set, the query shape and it's hits are highlighted.
+    /// The output is deterministic, so it can be used for snapshot tests.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The [`SvgOptions`] controlling the viewport and the query to highlight
+    ///
+    /// # Examples
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle, SvgOptions, SvgQuery};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(3u8, (2.0, 7.0));
+    /// quadtree.insert(2, (2.5, 7.5));
+    ///
+    /// let query = Rectangle::new((2.2, 7.2), 1.0, 1.0);
+    /// let svg = quadtree.to_svg(&SvgOptions::new(200.0, 400.0).with_query(SvgQuery::Rectangle(query)));
+    ///
+    /// assert!(svg.starts_with("<svg"));
+    /// assert_eq!(quadtree.node_count() + 1, svg.matches("<rect").count());
+    /// ```
+    ///
+    pub fn to_svg(&self, options: &SvgOptions) -> String {
+        let hits = match &options.query {
+            None => None,
+            Some(SvgQuery::Rectangle(rect)) => self.find_in_bounds_positions(rect),
+            Some(SvgQuery::Circle(center, radius)) => {
+                self.find_in_radius_positions(*center, *radius)
+            }
+        }
+        .unwrap_or_default();
+
+        svg::render(
+            &self.bounds,
+            &self.aabbs(),
+            self.iter_positions(),
+            &hits,
+            options,
+        )
+    }
+
+    /// Draw the tree onto a character grid, useful for logs and test failure messages.
+    /// Node bounds are drawn as lines, stored points as `*`.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - The number of characters per row
+    /// * `height` - The number of rows
+    ///
+    /// # Examples
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 8.0, 4.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(3u8, (1.0, 3.0));
+    /// quadtree.insert(2, (7.0, 1.0));
+    ///
+    /// let expected = "\
+    /// +-------+
+    /// |*      |
+    /// |       |
+    /// |      *|
+    /// +-------+
+    /// ";
+    /// assert_eq!(expected, quadtree.render_ascii(9, 5));
+    /// ```
+    ///
+    pub fn render_ascii(&self, width: usize, height: usize) -> String {
+        debug::render_ascii(
+            &self.bounds,
+            &self.aabbs(),
+            self.iter_positions(),
+            width,
+            height,
+        )
+    }
+
+    /// Get the leaf containing the given point.
+    /// A leaf is a quadrant of a node that holds no child node, so it is either empty or holds a single data point.
+    ///
+    /// # Arguments
+    ///
+    /// * `pt` - The point for which to find the leaf, or anything implementing `Into<Point>`
+    ///
+    /// # Examples
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 8.0, 8.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(3u8, (1.0, 1.0));
+    ///
+    /// let leaf = quadtree.leaf_at((2.0, 2.0)).unwrap();
+    /// assert_eq!(&Rectangle::new((0.0, 0.0), 4.0, 4.0), leaf.bounds());
+    /// assert_eq!(Some(&3), leaf.item().map(|(_, data)| data));
+    ///
+    /// assert!(quadtree.leaf_at((9.0, 2.0)).is_none());
+    /// ```
+    ///
+    pub fn leaf_at(&self, pt: impl Into<Point>) -> Option<Leaf<'_, T>> {
+        LeafPath::find(self.nodes.root(), &pt.into()).map(|path| path.leaf())
+    }
+
+    /// Get all leaves sharing an edge or corner with the leaf containing the given point,
+    /// in the given direction. Neighbors are found across parent node boundaries.
+    /// If the neighboring area is subdivided more finely, all leaves facing the leaf are returned,
+    /// otherwise there is a single neighbor, which can be larger than the leaf itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `pt` - A point inside of the leaf for which to find neighbors, or anything implementing `Into<Point>`
+    /// * `direction` - The [`Direction`] in which to look for neighbors
+    ///
+    /// # Examples
+    /// ```
+    /// use quadtree::{Direction, QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 8.0, 8.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// // split the bottom left quadrant of the root
+    /// quadtree.insert(3u8, (1.0, 1.0));
+    /// quadtree.insert(2, (1.0, 3.0));
+    ///
+    /// let west: Vec<Rectangle> = quadtree
+    ///     .neighbors((6.0, 2.0), Direction::West)
+    ///     .iter()
+    ///     .map(|leaf| *leaf.bounds())
+    ///     .collect();
+    /// assert_eq!(vec![Rectangle::new((2.0, 0.0), 2.0, 2.0), Rectangle::new((2.0, 2.0), 2.0, 2.0)], west);
+    ///
+    /// // there is nothing east of the tree bounds
+    /// assert!(quadtree.neighbors((6.0, 2.0), Direction::East).is_empty());
+    /// ```
+    ///
+    pub fn neighbors(&self, pt: impl Into<Point>, direction: Direction) -> Vec<Leaf<'_, T>> {
+        match LeafPath::find(self.nodes.root(), &pt.into()) {
+            None => Vec::new(),
+            Some(path) => path.neighbors(&direction),
+        }
+    }
+
+    /// Refine the tree until it satisfies the 2:1 balance condition,
+    /// so leaves sharing an edge differ by at most one level of subdivision.
+    /// This adds empty nodes, which will be removed again by [`QuadTree::shrink`].
+    ///
+    /// # Examples
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 8.0, 8.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// // two close points create a deep subdivision next to the center of the tree
+    /// quadtree.insert(3u8, (3.9, 3.9));
+    /// quadtree.insert(2, (3.9, 3.8));
+    /// assert_eq!(6, quadtree.node_count());
+    ///
+    /// quadtree.balance();
+    /// assert_eq!(17, quadtree.node_count());
+    /// assert_eq!(2, quadtree.len());
+    /// ```
+    ///
+    pub fn balance(&mut self) {
+        loop {
+            let unbalanced = mesh::unbalanced_leaves(self.nodes.root());
+            if unbalanced.is_empty() {
+                return;
+            }
+
+            for center in unbalanced {
+                self.nodes.subdivide_leaf_at(&center);
+            }
+        }
+    }
+
+    /// Create a conforming triangle mesh over all leaves of the tree.
+    /// Vertices on the edges of a leaf, introduced by finer neighbors, are shared by the triangles
+    /// of both leaves, so there are no hanging vertices in the mesh.
+    /// Call [`QuadTree::balance`] first, to get a mesh with well-shaped triangles.
+    ///
+    /// # Examples
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 8.0, 8.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(3u8, (3.9, 3.9));
+    /// quadtree.insert(2, (3.9, 3.8));
+    /// quadtree.balance();
+    ///
+    /// let mesh = quadtree.mesh();
+    /// assert_eq!(3 * mesh.triangle_count(), mesh.indices.len());
+    /// assert!(mesh.indices.iter().all(|i| (*i as usize) < mesh.vertices.len()));
+    /// ```
+    ///
+    pub fn mesh(&self) -> Mesh {
+        mesh::triangulate(self.nodes.root())
+    }
+
+    /// Get a read-only [`Cursor`] pointing at the root node of this tree
+    ///
+    /// # Examples
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let quadtree: QuadTree<u8> = QuadTree::new_bounded(&bounds);
+    ///
+    /// let cursor = quadtree.cursor();
+    /// assert_eq!(&bounds, cursor.bounds());
+    /// assert_eq!(0, cursor.depth());
+    /// assert!(cursor.parent().is_none());
+    /// ```
+    ///
+    pub fn cursor(&self) -> Cursor<'_, T> {
+        Cursor::new(self.nodes.root())
+    }
+
+    /// Walk the tree depth-first with a custom [`TreeVisitor`].
+    /// The visitor controls the walk by returning [`VisitControl`](crate::VisitControl) values,
+    /// so whole subtrees can be pruned, or the walk stopped early.
+    ///
+    /// # Arguments
+    ///
+    /// * `visitor` - The visitor to call for every visited node and data point
+    ///
+    /// # Examples
+    /// ```
+    /// use quadtree::{Cursor, Point, QuadTree, Rectangle, TreeVisitor, VisitControl};
+    ///
+    /// // collect all data, but don't descend below depth 1
+    /// struct Shallow(Vec<u8>);
+    ///
+    /// impl TreeVisitor<u8> for Shallow {
+    ///     fn visit_node(&mut self, cursor: &Cursor<'_, u8>) -> VisitControl {
+    ///         if cursor.depth() > 1 {
+    ///             VisitControl::SkipSubtree
+    ///         } else {
+    ///             VisitControl::Continue
+    ///         }
+    ///     }
+    ///
+    ///     fn visit_item(&mut self, _position: &Point, data: &u8) -> VisitControl {
+    ///         self.0.push(*data);
+    ///         VisitControl::Continue
+    ///     }
+    /// }
+    ///
+    /// let bounds = Rectangle::new((0.0, 0.0), 8.0, 8.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(1u8, (7.0, 7.0));
+    /// quadtree.insert(2, (3.0, 3.0));
+    /// quadtree.insert(3, (0.5, 0.5));
+    /// quadtree.insert(4, (0.5, 1.0));
+    ///
+    /// let mut visitor = Shallow(Vec::new());
+    /// quadtree.walk(&mut visitor);
+    /// assert_eq!(vec![2, 1], visitor.0);
+    /// ```
+    ///
+    pub fn walk<V: TreeVisitor<T> + ?Sized>(&self, visitor: &mut V) {
+        visitor::walk_cursor(visitor, &mut self.cursor());
+    }
+
+    /// Get the number of all data points *(or leaf nodes)* stored in this tree.
+    /// The count is cached, so this is O(1).
+    ///
+    /// # Examples
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// // len of empty tree is 0
+    /// assert_eq!(0, quadtree.len());
+    ///
+    /// quadtree.insert(3u8, (2.0, 7.0));
+    /// assert_eq!(1, quadtree.len());
+    ///
+    /// quadtree.insert(2, (2.5, 7.5));
+    /// assert_eq!(2, quadtree.len());
+    ///
+    /// quadtree.remove((2.5, 7.5));
+    /// assert_eq!(1, quadtree.len());
+    /// ```
+    ///
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Test if the tree contains no data points at all
+    ///
+    /// # Examples
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// assert!(quadtree.is_empty());
+    ///
+    /// quadtree.insert(3u8, (2.0, 7.0));
+    /// assert!(!quadtree.is_empty());
+    /// ```
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Collect statistics about the shape of this tree, useful to tune the tree bounds.
+    /// This walks the whole tree, see [`TreeStats`] for details on the collected values.
+    ///
+    /// # Examples
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// // insert two points close to each other, the root node has to split
+    /// quadtree.insert(3u8, (2.0, 7.0));
+    /// quadtree.insert(2, (2.5, 7.5));
+    ///
+    /// let stats = quadtree.stats();
+    /// assert_eq!(vec![1, 1, 1, 1], stats.depth_histogram);
+    /// assert_eq!(quadtree.node_count(), stats.node_count);
+    /// assert_eq!(13, stats.leaf_count);
+    /// assert_eq!(11, stats.empty_quadrant_count);
+    /// assert_eq!(1, stats.max_items_per_leaf);
+    /// ```
+    ///
+    pub fn stats(&self) -> TreeStats {
+        stats::collect(&self.nodes)
+    }
+
+    /// Returns an iterator over the positions of all data points in this tree
+    ///
+    /// # Examples
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle, Point};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(3u8, (2.0, 7.0));
+    /// quadtree.insert(2, (2.5, 7.5));
+    ///
+    /// let mut positions = quadtree.iter_positions();
+    /// assert_eq!(&Point::new(2.5, 7.5), positions.next().unwrap());
+    /// assert_eq!(&Point::new(2.0, 7.0), positions.next().unwrap());
+    /// assert!(positions.next().is_none());
+    /// ```
+    pub fn iter_positions(&self) -> impl Iterator<Item = &Point> {
+        self.nodes
+            .root()
+            .data_children()
+            .into_iter()
+            .map(|spatial| spatial.position())
+    }
+
+    /// Returns an iterator over the positions and data of all data points in this tree
+    ///
+    /// # Examples
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle, Point};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(3u8, (2.0, 7.0));
+    /// quadtree.insert(2, (2.5, 7.5));
+    ///
+    /// let mut items = quadtree.iter();
+    /// assert_eq!(Some((&Point::new(2.5, 7.5), &2)), items.next());
+    /// assert_eq!(Some((&Point::new(2.0, 7.0), &3)), items.next());
+    /// assert!(items.next().is_none());
+    /// ```
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter::new(self.nodes.root().data_children())
+    }
+
+    /// Returns an iterator over the data of all data points in this tree
+    ///
+    /// # Examples
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle, Point};
+    /// let bounds = Rectangle::new((0.0, 0.0), 5.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(3u8, (2.0, 7.0));
+    /// quadtree.insert(2, (2.5, 7.5));
+    ///
+    /// let mut positions = quadtree.iter_data();
+    /// assert_eq!(&2, positions.next().unwrap());
+    /// assert_eq!(&3, positions.next().unwrap());
+    /// assert!(positions.next().is_none());
+    /// ```
+    pub fn iter_data(&self) -> impl Iterator<Item = &T> {
+        self.nodes
+            .root()
+            .data_children()
+            .into_iter()
+            .map(|spatial| spatial.data())
+    }
+}
+
+/// Parallel queries, which split their work across scoped threads.
+/// They need `T` to be [`Sync`], so it can be read from multiple threads at once.
+impl<T> QuadTree<T>
+where
+    T: Sync,
+{
+    /// Like [`QuadTree::find_in_bounds`], but searches the four quadrants of the root node in parallel.
+    /// The order of the results can differ from [`QuadTree::find_in_bounds`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(3u8, (2.0, 7.0));
+    /// quadtree.insert(2, (7.5, 7.5));
+    /// quadtree.insert(1, (8.0, 1.0));
+    ///
+    /// let query = Rectangle::new((1.0, 5.0), 8.0, 4.0);
+    /// let mut found = quadtree.par_find_in_bounds(&query).unwrap();
+    /// found.sort();
+    /// assert_eq!(vec![&2, &3], found);
+    /// ```
+    ///
+    pub fn par_find_in_bounds(&self, bounds: &Rectangle) -> Option<Vec<&T>> {
+        self.query_parts(bounds, |part| self.par_find_part(part))
+    }
+
+    fn par_find_part(&self, bounds: &Rectangle) -> Option<Vec<&T>> {
+        let root = self.nodes.root();
+        if root.bounds().relation(bounds) == RectangleRelation::Disjoint {
+            return None;
+        }
+
+        let found = parallel::map(&Quadrant::ALL, |quadrant| match root.quadrant(quadrant) {
+            None => Vec::new(),
+            Some(TreeNode::Point(data)) => match bounds.find_quadrant(data.position()) {
+                None => Vec::new(),
+                Some(_) => vec![data.data()],
+            },
+            Some(TreeNode::Bucket(bucket)) => {
+                let mut found = Vec::new();
+                bucket.filter_in_bounds(bounds, &mut found);
+                found.into_iter().map(|d| d.data()).collect()
+            }
+            Some(TreeNode::Node(id)) => root
+                .get(*id)
+                .find_in_bounds(bounds)
+                .map(|data| data.iter().map(|d| d.data()).collect())
+                .unwrap_or_default(),
+        });
+
+        Some(found.into_iter().flatten().collect())
+    }
+
+    /// Call a closure on the position and data of every data point in the tree,
+    /// visiting the four quadrants of the root node in parallel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(3usize, (2.0, 7.0));
+    /// quadtree.insert(2, (7.5, 7.5));
+    ///
+    /// let sum = AtomicUsize::new(0);
+    /// quadtree.par_for_each(|_position, data| {
+    ///     sum.fetch_add(*data, Ordering::Relaxed);
+    /// });
+    /// assert_eq!(5, sum.into_inner());
+    /// ```
+    ///
+    pub fn par_for_each<F>(&self, f: F)
+    where
+        F: Fn(&Point, &T) + Sync,
+    {
+        let root = self.nodes.root();
+        parallel::map(&Quadrant::ALL, |quadrant| match root.quadrant(quadrant) {
+            None => (),
+            Some(TreeNode::Point(data)) => f(data.position(), data.data()),
+            Some(TreeNode::Bucket(bucket)) => bucket
+                .items()
+                .iter()
+                .for_each(|data| f(data.position(), data.data())),
+            Some(TreeNode::Node(id)) => root
+                .get(*id)
+                .visit_data_ref(&mut |data| f(data.position(), data.data())),
+        });
+    }
+
+    /// Run [`QuadTree::find_in_bounds`] for many query rectangles in parallel.
+    /// The results are in the same order as the queries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle};
+    /// let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(3u8, (2.0, 7.0));
+    /// quadtree.insert(2, (7.5, 7.5));
+    ///
+    /// let queries = [
+    ///     Rectangle::new((1.0, 6.0), 2.0, 2.0),
+    ///     Rectangle::new((20.0, 20.0), 1.0, 1.0),
+    /// ];
+    /// assert_eq!(vec![Some(vec![&3]), None], quadtree.find_in_bounds_many(&queries));
+    /// ```
+    ///
+    pub fn find_in_bounds_many(&self, queries: &[Rectangle]) -> Vec<Option<Vec<&T>>> {
+        parallel::map(queries, |bounds| self.find_in_bounds(bounds))
+    }
+
+    /// Run [`QuadTree::k_nearest`] for many test points in parallel.
+    /// The results are in the same order as the test points.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{QuadTree, Rectangle, Point};
+    /// let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+    /// let mut quadtree = QuadTree::new_bounded(&bounds);
+    ///
+    /// quadtree.insert(3u8, (2.0, 7.0));
+    /// quadtree.insert(2, (7.5, 7.5));
+    ///
+    /// let points = [Point::new(1.0, 1.0), Point::new(9.0, 9.0)];
+    /// assert_eq!(vec![vec![&3], vec![&2]], quadtree.k_nearest_many(&points, 1));
+    /// ```
+    ///
+    pub fn k_nearest_many(&self, points: &[Point], k: usize) -> Vec<Vec<&T>> {
+        parallel::map(points, |pt| self.k_nearest(*pt, k))
+    }
+}
+
+impl<T> Debug for QuadTree<T>
+where
+    T: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QuadTree")
+            .field("bounds", &self.bounds)
+            .field(
+                "root",
+                &DebugNode {
+                    node: self.nodes.root(),
+                    depth: 0,
+                },
+            )
+            .finish()
+    }
+}
+
+/// Compares the stored positions and data, ignoring the bounds and internal layout of the trees
+impl<T> PartialEq for QuadTree<T>
+where
+    T: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len
+            && self
+                .iter()
+                .all(|(position, data)| other.find_exact(*position) == Some(data))
+    }
+}
+
+impl<T> Eq for QuadTree<T> where T: Eq {}
+
+/// An empty tree with empty bounds, which grow when the tree is extended
+impl<T> Default for QuadTree<T> {
+    fn default() -> Self {
+        Self::new_bounded(&Rectangle::new((0.0, 0.0), 0.0, 0.0))
+    }
+}
+
+impl<T> IntoIterator for QuadTree<T> {
+    type Item = (Point, T);
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(mut self) -> Self::IntoIter {
+        IntoIter::new(self.nodes.collapse())
+    }
+}
+
+impl<'tree, T> IntoIterator for &'tree QuadTree<T> {
+    type Item = (&'tree Point, &'tree T);
+    type IntoIter = Iter<'tree, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Inserts all data like [`QuadTree::insert`], skipping data at positions already in the tree.
+///
+/// If any new data lies outside of the bounds of the tree, the tree is rebuilt once
+/// with bounds grown to contain it. Empty bounds, like those of [`QuadTree::default`], are replaced.
+/// Wrapping trees keep their bounds, as they describe the space wrapping around,
+/// so data outside of them is skipped like by [`QuadTree::insert`].
+impl<T, P> Extend<(P, T)> for QuadTree<T>
+where
+    P: Into<Point>,
+{
+    fn extend<I: IntoIterator<Item = (P, T)>>(&mut self, iter: I) {
+        let data: Vec<Spatial<T>> = iter
+            .into_iter()
+            .map(|(position, data)| (data, position.into()).into())
+            .collect();
+
+        // positions which are not finite are skipped when growing, and rejected on insertion
+        let boundary = self.bounds.boundary();
+        let outside = data
+            .iter()
+            .map(|data| data.position())
+            .filter(|position| self.bounds.find_quadrant(position).is_none());
+        if !self.wrapping {
+            if let Some(grown) = Rectangle::bounding(outside, boundary) {
+                let empty =
+                    self.is_empty() && self.bounds.width() == 0.0 && self.bounds.height() == 0.0;
+                let bounds = if empty {
+                    Rectangle::bounding(data.iter().map(|data| data.position()), boundary)
+                        .expect("Data can not be empty here")
+                } else {
+                    self.bounds.union(&grown)
+                };
+                self.rebound(&bounds);
+            }
+        }
+
+        for data in data {
+            let _ = self.try_insert_spatial(data);
+        }
+    }
+}
+
+/// Creates a tree bounded by the smallest rectangle containing all data, see [`Extend`]
+///
+/// # Examples
+///
+/// ```
+/// use quadtree::{Point, QuadTree};
+/// let quadtree: QuadTree<u8> = vec![((1.0, 1.0), 3), ((4.0, -2.0), 2)].into_iter().collect();
+///
+/// assert_eq!(2, quadtree.len());
+/// assert_eq!(Some(&2), quadtree.find_exact((4.0, -2.0)));
+/// ```
+impl<T, P> FromIterator<(P, T)> for QuadTree<T>
+where
+    P: Into<Point>,
+{
+    fn from_iter<I: IntoIterator<Item = (P, T)>>(iter: I) -> Self {
+        let mut quadtree = Self::default();
+        quadtree.extend(iter);
+        quadtree
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn quadtree_can_insert() {
+        // Arrange
+        let bounds: Rectangle = Rectangle::new((0.0, 0.0), 10.0, 20.0);
+        let mut quadtree: QuadTree<u8> = QuadTree::new_bounded(&bounds);
+
+        // insert a data point unchecked
+        quadtree.insert_unchecked(4, (0.0, 0.5));
+
+        // assert it's where we assume it to be
+        assert!(quadtree.contains((0.0, 0.5)));
+        assert_eq!(Some(&4u8), quadtree.find_exact((0.0, 0.5)));
+
+        quadtree.insert(2, (1.0, 1.0));
+        quadtree.insert(2, (2.0, 1.0));
+        quadtree.insert(2, (0.5, 1.0));
+
+        assert_eq!(5, quadtree.node_count());
+        assert_eq!(4, quadtree.len());
+    }
+
+    #[test]
+    fn quadtree_debug_prints_hierarchy() {
+        // Arrange
+        let bounds: Rectangle = Rectangle::new((0.0, 0.0), 10.0, 20.0);
+        let mut quadtree: QuadTree<u8> = QuadTree::new_bounded(&bounds);
+        quadtree.insert(1, (1.0, 1.0));
+        quadtree.insert(2, (2.0, 1.0));
+
+        // Act
+        let text = format!("{:#?}", quadtree);
+
+        // Assert
+        assert!(text.starts_with("QuadTree {"));
+        assert!(text.contains("depth: 2"));
+        assert!(text.contains("data: 2"));
+    }
+
+    #[test]
+    fn quadtree_caches_len_and_node_count() {
+        // Arrange
+        let bounds: Rectangle = Rectangle::new((0.0, 0.0), 10.0, 20.0);
+        let mut quadtree: QuadTree<u8> = QuadTree::new_bounded(&bounds);
+        for i in 0..10 {
+            quadtree.insert(i, (i as f32 * 0.1, 1.0));
+        }
+        quadtree.remove((0.0, 1.0));
+        quadtree.remove((0.5, 1.0));
+        assert!(!quadtree.insert(2, (-1.0, 1.0)));
+
+        // Assert
+        assert_eq!(8, quadtree.len());
+        assert_eq!(quadtree.stats().node_count, quadtree.node_count());
+
+        quadtree.shrink();
+        assert_eq!(8, quadtree.len());
+        assert_eq!(quadtree.stats().node_count, quadtree.node_count());
+
+        quadtree.clear();
+        assert_eq!(0, quadtree.len());
+        assert_eq!(1, quadtree.node_count());
+    }
+
+    #[test]
+    fn quadtree_compressed_answers_like_plain() {
+        // Arrange
+        let bounds: Rectangle = Rectangle::new((0.0, 0.0), 16.0, 16.0);
+        let mut plain: QuadTree<usize> = QuadTree::new_bounded(&bounds);
+        let mut compressed: QuadTree<usize> =
+            QuadTreeBuilder::new(&bounds).compressed(true).build();
+
+        let mut positions: Vec<Point> = Vec::new();
+        for i in 0..8 {
+            for j in 0..8 {
+                positions.push(Point::new(i as f32 * 2.0 + 0.3, j as f32 * 2.0 + 0.7));
+            }
+            // near duplicates, which create long chains in a plain tree
+            positions.push(Point::new(5.3 + i as f32 * 0.0001, 5.3));
+        }
+        for (i, position) in positions.iter().enumerate() {
+            assert!(plain.insert(i, *position));
+            assert!(compressed.insert(i, *position));
+        }
+
+        let sorted = |mut points: Vec<Point>| {
+            points.sort_by(|a, b| (a.x(), a.y()).partial_cmp(&(b.x(), b.y())).unwrap());
+            points
+        };
+
+        // Assert
+        assert!(compressed.node_count() < positions.len());
+        assert!(compressed.node_count() < plain.node_count());
+        for query in [
+            Rectangle::new((0.0, 0.0), 16.0, 16.0),
+            Rectangle::new((5.0, 5.0), 0.5, 0.5),
+            Rectangle::new((3.1, 2.2), 7.5, 3.3),
+        ]
+        .iter()
+        {
+            assert_eq!(
+                sorted(plain.find_in_bounds_positions(query).unwrap()),
+                sorted(compressed.find_in_bounds_positions(query).unwrap())
+            );
+        }
+        for pt in [
+            Point::new(5.3, 5.31),
+            Point::new(15.0, 1.0),
+            Point::new(8.0, 8.0),
+        ]
+        .iter()
+        {
+            assert_eq!(
+                plain.find_nearest_neighbor_position(*pt),
+                compressed.find_nearest_neighbor_position(*pt)
+            );
+        }
+
+        // removing data keeps the tree compressed after shrinking
+        for position in positions.iter().skip(1).step_by(2) {
+            assert!(compressed.remove(*position).is_some());
+        }
+        compressed.shrink();
+        assert!(compressed.is_compressed());
+        assert_eq!(positions.len() / 2, compressed.len());
+        assert!(compressed.node_count() < compressed.len());
+        assert_eq!(Some(&0), compressed.find_exact(positions[0]));
+    }
+
+    #[test]
+    fn quadtree_buckets_answer_like_plain() {
+        // Arrange
+        let bounds: Rectangle = Rectangle::new((0.0, 0.0), 16.0, 16.0);
+        let mut plain: QuadTree<usize> = QuadTree::new_bounded(&bounds);
+        let mut bucketed: Vec<QuadTree<usize>> = vec![
+            QuadTreeBuilder::new(&bounds).bucket_capacity(8).build(),
+            QuadTreeBuilder::new(&bounds)
+                .bucket_capacity(4)
+                .compressed(true)
+                .build(),
+        ];
+
+        let mut positions: Vec<Point> = Vec::new();
+        for i in 0..12 {
+            for j in 0..12 {
+                positions.push(Point::new(i as f32 * 1.3 + 0.2, j as f32 * 1.1 + 0.4));
+            }
+            positions.push(Point::new(5.3 + i as f32 * 0.0001, 5.3));
+        }
+        for (i, position) in positions.iter().enumerate() {
+            assert!(plain.insert(i, *position));
+            for quadtree in bucketed.iter_mut() {
+                assert!(quadtree.insert(i, *position));
+            }
+        }
+
+        let sorted = |mut points: Vec<Point>| {
+            points.sort_by(|a, b| (a.x(), a.y()).partial_cmp(&(b.x(), b.y())).unwrap());
+            points
+        };
+
+        // Assert
+        for quadtree in bucketed.iter_mut() {
+            assert_eq!(positions.len(), quadtree.len());
+            assert!(quadtree.node_count() < plain.node_count());
+            assert_eq!(quadtree.stats().node_count, quadtree.node_count());
+            for query in [
+                Rectangle::new((0.0, 0.0), 16.0, 16.0),
+                Rectangle::new((5.0, 5.0), 0.5, 0.5),
+                Rectangle::new((3.1, 2.2), 7.5, 3.3),
+            ]
+            .iter()
+            {
+                assert_eq!(
+                    sorted(plain.find_in_bounds_positions(query).unwrap()),
+                    sorted(quadtree.find_in_bounds_positions(query).unwrap())
+                );
+                let positions = |chunked: bool| -> Vec<Point> {
+                    quadtree
+                        .scan_spatial_in_bounds(query, chunked)
+                        .iter()
+                        .map(|data| *data.position())
+                        .collect()
+                };
+                let scanned = positions(true);
+                assert_eq!(scanned, positions(false));
+                assert_eq!(plain.find_in_bounds(query).unwrap().len(), scanned.len());
+            }
+            for pt in [
+                Point::new(5.3, 5.31),
+                Point::new(15.0, 1.0),
+                Point::new(8.0, 8.0),
+            ]
+            .iter()
+            {
+                assert_eq!(
+                    sorted(plain.find_in_radius_positions(*pt, 2.5).unwrap()),
+                    sorted(quadtree.find_in_radius_positions(*pt, 2.5).unwrap())
+                );
+                assert_eq!(
+                    plain.find_nearest_neighbor_position(*pt),
+                    quadtree.find_nearest_neighbor_position(*pt)
+                );
+                assert_eq!(
+                    plain.k_nearest_positions(*pt, 5),
+                    quadtree.k_nearest_positions(*pt, 5)
+                );
+            }
+            assert_eq!(Some(&7), quadtree.find_exact(positions[7]));
+
+            // removing data empties buckets, which are dropped after shrinking
+            for position in positions.iter().skip(1).step_by(2) {
+                assert!(quadtree.remove(*position).is_some());
+                assert!(quadtree.find_exact(*position).is_none());
+            }
+            quadtree.shrink();
+            assert_eq!(positions.len() / 2, quadtree.len());
+            assert_eq!(Some(&0), quadtree.find_exact(positions[0]));
+        }
+    }
+
+    #[test]
+    fn quadtree_bulk_removal_restructures_once() {
+        // Arrange
+        let bounds: Rectangle = Rectangle::new((0.0, 0.0), 16.0, 16.0);
+        let builders = [
+            QuadTreeBuilder::new(&bounds),
+            QuadTreeBuilder::new(&bounds).bucket_capacity(4),
+            QuadTreeBuilder::new(&bounds).compressed(true),
+        ];
+        let mut positions: Vec<Point> = Vec::new();
+        for i in 0..10 {
+            for j in 0..10 {
+                positions.push(Point::new(i as f32 * 1.6 + 0.3, j as f32 * 1.5 + 0.2));
+            }
+            positions.push(Point::new(9.3 + i as f32 * 0.0001, 9.3));
+        }
+        let query = Rectangle::new((2.0, 2.0), 9.0, 9.0);
+        let sorted = |mut points: Vec<Point>| {
+            points.sort_by(|a, b| (a.x(), a.y()).partial_cmp(&(b.x(), b.y())).unwrap());
+            points
+        };
+
+        for builder in builders.iter() {
+            let mut quadtree: QuadTree<usize> = builder.build();
+            for (i, position) in positions.iter().enumerate() {
+                quadtree.insert(i, *position);
+            }
+            let expected = sorted(quadtree.find_in_bounds_positions(&query).unwrap());
+
+            // Act
+            let removed = quadtree.remove_in_bounds(&query);
+            quadtree.retain(|_, data| *data % 3 != 0);
+
+            // Assert
+            assert_eq!(
+                expected,
+                sorted(removed.iter().map(|(position, _)| *position).collect())
+            );
+            assert!(quadtree.find_in_bounds(&query).unwrap().is_empty());
+            assert_eq!(quadtree.iter_data().count(), quadtree.len());
+            assert!(quadtree.iter_data().all(|data| *data % 3 != 0));
+
+            // the result has as few nodes as building the tree from the remaining data
+            let mut rebuilt: QuadTree<usize> = builder.build();
+            for (position, data) in quadtree.iter_positions().zip(quadtree.iter_data()) {
+                rebuilt.insert(*data, *position);
+            }
+            assert_eq!(rebuilt.node_count(), quadtree.node_count());
+            assert_eq!(quadtree.stats().node_count, quadtree.node_count());
+
+            let len = quadtree.len();
+            assert_eq!(len, quadtree.drain().count());
+            assert!(quadtree.is_empty());
+            assert_eq!(1, quadtree.node_count());
+        }
+    }
+
+    #[test]
+    fn quadtree_implements_collection_traits() {
+        // Arrange
+        let data: Vec<(Point, usize)> = (0..50)
+            .map(|i| (Point::new(i as f32 * 0.7 - 3.0, (i % 7) as f32 * 1.3), i))
+            .collect();
+
+        // Act
+        let collected: QuadTree<usize> = data.iter().cloned().collect();
+        let compressed: QuadTree<usize> =
+            QuadTreeBuilder::new(&Rectangle::new((0.0, 0.0), 1.0, 1.0))
+                .compressed(true)
+                .bucket_capacity(3)
+                .build_from(data.iter().cloned().rev());
+        let cloned = collected.clone();
+
+        // Assert
+        assert_eq!(data.len(), collected.len());
+        assert_eq!(data.len(), compressed.len());
+        assert!(data
+            .iter()
+            .all(|(position, i)| collected.find_exact(*position) == Some(i)));
+        assert!(collected == compressed);
+        assert!(cloned == collected);
+        assert_eq!(collected.node_count(), cloned.node_count());
+        assert_eq!(data.len(), (&collected).into_iter().count());
+
+        let mut owned: Vec<(Point, usize)> = collected.into_iter().collect();
+        owned.sort_by_key(|(_, i)| *i);
+        assert_eq!(data, owned);
+
+        // extending grows the bounds and skips duplicates
+        let mut extended = cloned.clone();
+        extended.extend(vec![(Point::new(100.0, 100.0), 1000), (data[5].0, 1001)]);
+        assert_eq!(data.len() + 1, extended.len());
+        assert_eq!(Some(&1000), extended.find_exact((100.0, 100.0)));
+        assert!(extended != cloned);
+
+        let empty: QuadTree<usize> = QuadTree::default();
+        assert!(empty.is_empty());
+        assert!(empty == QuadTree::new_bounded(&Rectangle::new((5.0, 5.0), 1.0, 1.0)));
+    }
+
+    #[test]
+    fn quadtree_approximate_lookup_matches_brute_force() {
+        // Arrange
+        let bounds: Rectangle = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+        let mut quadtree: QuadTree<usize> = QuadTreeBuilder::new(&bounds)
+            .tolerance(0.05)
+            .bucket_capacity(4)
+            .build();
+        let positions: Vec<Point> = (0..200)
+            .map(|i| {
+                Point::new(
+                    (i * 37 % 100) as f32 * 0.09 + 0.5,
+                    (i * 53 % 97) as f32 * 0.09 + 0.5,
+                )
+            })
+            .collect();
+
+        // Act
+        let mut inserted = 0;
+        for (i, position) in positions.iter().enumerate() {
+            quadtree.insert_or_merge(i, *position, |_, _| ());
+            // a slightly moved copy of every point is merged into the original
+            let moved = Point::new(position.x() + 0.01, position.y() - 0.02);
+            quadtree.insert_or_merge(1000, moved, |count, _| *count += 1000);
+            inserted += 1;
+        }
+
+        // Assert
+        assert_eq!(inserted, quadtree.len());
+        for (i, position) in positions.iter().enumerate() {
+            assert_eq!(Some(&(i + 1000)), quadtree.find_approx(*position, 0.001));
+            assert_eq!(
+                Some(*position),
+                quadtree.find_approx_position((position.x() + 0.03, position.y()), 0.05)
+            );
+            assert!(!quadtree.insert(0, (position.x(), position.y() + 0.04)));
+        }
+        let pt = Point::new(4.33, 2.61);
+        let expected = positions
+            .iter()
+            .filter(|position| position.squared_distance(&pt) <= 0.25 * 0.25)
+            .min_by(|a, b| {
+                a.squared_distance(&pt)
+                    .partial_cmp(&b.squared_distance(&pt))
+                    .unwrap()
+            });
+        assert_eq!(expected.copied(), quadtree.find_approx_position(pt, 0.25));
+        assert!(!quadtree.contains_approx((20.0, 20.0), 1.0));
+    }
+
+    #[test]
+    fn quadtree_half_open_tiles_never_share_points() {
+        // Arrange
+        let closed = Rectangle::new((0.0, 0.0), 8.0, 8.0);
+        let half_open = closed.with_boundary(Boundary::HalfOpen);
+        let builders = [
+            QuadTreeBuilder::new(&closed),
+            QuadTreeBuilder::new(&half_open),
+            QuadTreeBuilder::new(&half_open).compressed(true),
+            QuadTreeBuilder::new(&half_open).bucket_capacity(4),
+        ];
+        // a grid with points on every tile edge and node center line
+        let positions: Vec<Point> = (0..16)
+            .flat_map(|i| (0..16).map(move |j| Point::new(i as f32 * 0.5, j as f32 * 0.5)))
+            .collect();
+        let tiles: Vec<Rectangle> = (0..4)
+            .flat_map(|i| {
+                (0..4).map(move |j| {
+                    Rectangle::new((i as f32 * 2.0, j as f32 * 2.0), 2.0, 2.0)
+                        .with_boundary(Boundary::HalfOpen)
+                })
+            })
+            .collect();
+
+        for builder in builders.iter() {
+            let mut quadtree: QuadTree<usize> = builder.build();
+            for (i, position) in positions.iter().enumerate() {
+                assert!(quadtree.insert(i, *position));
+            }
+
+            // Act
+            let mut found: Vec<usize> = tiles
+                .iter()
+                .flat_map(|tile| quadtree.find_in_bounds(tile).unwrap())
+                .copied()
+                .collect();
+
+            // Assert
+            found.sort_unstable();
+            assert_eq!((0..positions.len()).collect::<Vec<_>>(), found);
+            assert!(positions
+                .iter()
+                .all(|position| quadtree.contains(*position)));
+            assert_eq!(
+                builder.bounds.boundary() == Boundary::Closed,
+                quadtree.insert(1000, (8.0, 3.0))
+            );
+            assert_eq!(
+                Err(InsertError::NonFinite),
+                quadtree.try_insert(1000, (f32::NAN, 3.0))
+            );
+            assert_eq!(
+                Err(InsertError::NonFinite),
+                quadtree.try_insert(1000, (3.0, f32::INFINITY))
+            );
+        }
+    }
+
+    #[test]
+    fn quadtree_extend_keeps_wrapping_bounds() {
+        // Arrange
+        let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+        let mut quadtree: QuadTree<u8> = QuadTreeBuilder::new(&bounds).wrapping(true).build();
+        quadtree.insert(1, (9.8, 5.0));
+
+        // Act
+        quadtree.extend(vec![((0.5, 5.0), 2), ((12.0, 5.0), 3), ((-1.0, -1.0), 4)]);
+
+        // Assert
+        assert_eq!(bounds.with_boundary(Boundary::HalfOpen), quadtree.bounds);
+        assert_eq!(2, quadtree.len());
+        assert_eq!(None, quadtree.find_exact((12.0, 5.0)));
+        assert_eq!(Some(&1), quadtree.find_nearest_neighbor((0.0, 5.0)));
+    }
+
+    #[test]
+    fn quadtree_wrapping_duplicates_across_edges() {
+        // Arrange
+        let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+        let mut quadtree: QuadTree<u8> = QuadTreeBuilder::new(&bounds)
+            .wrapping(true)
+            .tolerance(0.01)
+            .build();
+
+        // Act
+        let first = quadtree.insert(1, (9.999, 5.0));
+        let seam = quadtree.insert(2, (0.0, 5.0));
+        let edge = quadtree.insert(3, (10.0, 5.0));
+        let merged = quadtree.insert_or_merge(4, (0.002, 5.0), |stored, new| *stored += new);
+
+        // Assert
+        assert_eq!(Boundary::HalfOpen, quadtree.bounds.boundary());
+        assert!(first);
+        assert!(!seam);
+        assert!(!edge);
+        assert!(merged);
+        assert_eq!(1, quadtree.len());
+        assert_eq!(Some(&5), quadtree.find_approx((0.0, 5.0), 0.01));
+    }
+
+    #[test]
+    fn quadtree_wrapping_queries_match_brute_force() {
+        // Arrange
+        let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+        let builders = [
+            QuadTreeBuilder::new(&bounds).wrapping(true),
+            QuadTreeBuilder::new(&bounds)
+                .wrapping(true)
+                .bucket_capacity(4),
+        ];
+        let positions: Vec<Point> = (0..200usize)
+            .map(|i| Point::new((i * 37 % 100) as f32 / 10.0, (i * 61 % 97) as f32 / 9.7))
+            .collect();
+        let torus = |a: &Point, b: &Point| {
+            let dx = (a.x() - b.x()).abs();
+            let dy = (a.y() - b.y()).abs();
+            dx.min(10.0 - dx).powi(2) + dy.min(10.0 - dy).powi(2)
+        };
+        let tests = [
+            Point::new(0.1, 0.1),
+            Point::new(9.9, 5.0),
+            Point::new(5.0, 9.95),
+            Point::new(4.0, 6.0),
+        ];
+
+        for builder in builders.iter() {
+            let mut quadtree: QuadTree<usize> = builder.build();
+            for (i, position) in positions.iter().enumerate() {
+                quadtree.insert(i, *position);
+            }
+
+            for test in tests.iter() {
+                // Act
+                let nearest = quadtree.k_nearest_positions(*test, 5);
+                let mut in_radius = quadtree.find_in_radius(*test, 1.5).unwrap();
+
+                // Assert
+                let mut expected: Vec<f32> = positions.iter().map(|p| torus(p, test)).collect();
+                expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                let distances: Vec<f32> = nearest.iter().map(|p| torus(p, test)).collect();
+                assert_eq!(expected[..5].to_vec(), distances);
+
+                let mut expected: Vec<usize> = (0..positions.len())
+                    .filter(|i| torus(&positions[*i], test) <= 1.5 * 1.5)
+                    .collect();
+                in_radius.sort_unstable();
+                expected.sort_unstable();
+                assert_eq!(expected, in_radius.into_iter().copied().collect::<Vec<_>>());
+            }
+
+            // a query crossing the top right corner continues at the other edges, without duplicates
+            let query = Rectangle::new((8.5, 9.0), 3.0, 2.0);
+            let mut found: Vec<usize> = quadtree
+                .find_in_bounds(&query)
+                .unwrap()
+                .into_iter()
+                .copied()
+                .collect();
+            found.sort_unstable();
+            let expected: Vec<usize> = (0..positions.len())
+                .filter(|i| {
+                    let p = positions[*i];
+                    (p.x() >= 8.5 || p.x() <= 1.5) && (p.y() >= 9.0 || p.y() <= 1.0)
+                })
+                .collect();
+            assert_eq!(expected, found);
+            assert!(!quadtree.insert(1000, (10.5, 3.0)));
+        }
+    }
+
+    #[test]
+    fn quadtree_wrapping_queries_outside_of_bounds() {
+        // Arrange
+        let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+        let mut line: QuadTree<u8> = QuadTreeBuilder::new(&bounds).wrapping(true).build();
+        line.insert(1, (9.5, 5.0));
+        line.insert(2, (0.5, 5.0));
+        line.insert(3, (4.0, 5.0));
+        line.insert(4, (5.0, 5.0));
+
+        let builders = [
+            QuadTreeBuilder::new(&bounds).wrapping(true),
+            QuadTreeBuilder::new(&bounds)
+                .wrapping(true)
+                .bucket_capacity(4),
+        ];
+        let positions: Vec<Point> = (0..200usize)
+            .map(|i| Point::new((i * 37 % 100) as f32 / 10.0, (i * 61 % 97) as f32 / 9.7))
+            .collect();
+        let torus = |a: &Point, b: &Point| {
+            let dx = (a.x() - b.x()).rem_euclid(10.0);
+            let dy = (a.y() - b.y()).rem_euclid(10.0);
+            dx.min(10.0 - dx).powi(2) + dy.min(10.0 - dy).powi(2)
+        };
+        let tests = [
+            Point::new(34.0, 5.0),
+            Point::new(-25.5, 2.0),
+            Point::new(4.0, -41.0),
+            Point::new(-19.9, 59.9),
+        ];
+
+        // Act
+        let mut right = line.find_in_radius((24.0, 5.0), 1.0).unwrap();
+        let mut left = line.find_in_radius((-20.0, 5.0), 1.0).unwrap();
+        right.sort_unstable();
+        left.sort_unstable();
+
+        // Assert
+        assert_eq!(vec![&3, &4], right);
+        assert_eq!(vec![&1, &2], left);
+        assert_eq!(Some(&3), line.find_nearest_neighbor((24.0, 5.0)));
+        assert_eq!(2, line.count_in_radius((-20.0, 5.0), 1.0));
+
+        for builder in builders.iter() {
+            let mut quadtree: QuadTree<usize> = builder.build();
+            for (i, position) in positions.iter().enumerate() {
+                quadtree.insert(i, *position);
+            }
+
+            for test in tests.iter() {
+                // Act
+                let nearest = quadtree.k_nearest_positions(*test, 5);
+                let nearest_by = quadtree.k_nearest_by(*test, 5, &crate::Euclidean);
+                let mut in_radius = quadtree.find_in_radius(*test, 1.5).unwrap();
+                let count = quadtree.count_in_radius(*test, 1.5);
+
+                // Assert
+                let mut expected: Vec<f32> = positions.iter().map(|p| torus(p, test)).collect();
+                expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                let distances: Vec<f32> = nearest.iter().map(|p| torus(p, test)).collect();
+                assert_eq!(expected[..5].to_vec(), distances);
+                let distances: Vec<f32> = nearest_by
+                    .iter()
+                    .map(|i| torus(&positions[**i], test))
+                    .collect();
+                assert_eq!(expected[..5].to_vec(), distances);
+
+                let mut expected: Vec<usize> = (0..positions.len())
+                    .filter(|i| torus(&positions[*i], test) <= 1.5 * 1.5)
+                    .collect();
+                in_radius.sort_unstable();
+                expected.sort_unstable();
+                assert_eq!(expected.len(), count);
+                assert_eq!(expected, in_radius.into_iter().copied().collect::<Vec<_>>());
+            }
+        }
+    }
+
+    #[test]
+    fn quadtree_metric_queries_match_brute_force() {
+        // Arrange
+        let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+        let metrics: [&dyn Metric; 4] = [
+            &crate::Euclidean,
+            &crate::Manhattan,
+            &crate::Chebyshev,
+            &crate::Weighted::new(0.5, 2.0),
+        ];
+        let mut quadtree: QuadTree<usize> =
+            QuadTreeBuilder::new(&bounds).bucket_capacity(4).build();
+        let positions: Vec<Point> = (0..200usize)
+            .map(|i| Point::new((i * 37 % 100) as f32 / 10.0, (i * 61 % 97) as f32 / 9.7))
+            .collect();
+        for (i, position) in positions.iter().enumerate() {
+            quadtree.insert(i, *position);
+        }
+        let test = Point::new(3.3, 6.1);
+
+        for metric in metrics.iter() {
+            // Act
+            let nearest = quadtree.k_nearest_positions_by(test, 7, *metric);
+            let mut in_radius = quadtree.find_in_radius_by(test, 1.2, *metric).unwrap();
+
+            // Assert
+            let mut expected: Vec<f32> = positions
+                .iter()
+                .map(|p| metric.distance(&test, p))
+                .collect();
+            expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            let distances: Vec<f32> = nearest.iter().map(|p| metric.distance(&test, p)).collect();
+            assert_eq!(expected[..7].to_vec(), distances);
+
+            let expected: Vec<usize> = (0..positions.len())
+                .filter(|i| metric.distance(&test, &positions[*i]) <= 1.2)
+                .collect();
+            in_radius.sort_unstable();
+            assert_eq!(expected, in_radius.into_iter().copied().collect::<Vec<_>>());
+        }
+        assert_eq!(
+            None,
+            quadtree.find_in_radius_by((20.0, 20.0), 1.0, &crate::Manhattan)
+        );
+
+        // wrapping trees measure around the edges
+        let mut wrapping: QuadTree<u8> = QuadTreeBuilder::new(&bounds).wrapping(true).build();
+        wrapping.insert(1, (0.5, 0.5));
+        wrapping.insert(2, (7.0, 9.0));
+        assert_eq!(
+            vec![&1, &2],
+            wrapping.k_nearest_by((9.5, 9.5), 2, &crate::Manhattan)
+        );
+        assert_eq!(
+            Some(vec![&1]),
+            wrapping.find_in_radius_by((9.5, 9.5), 1.0, &crate::Chebyshev)
+        );
+    }
+
+    #[test]
+    fn quadtree_filtered_nearest_matches_brute_force() {
+        // Arrange
+        let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+        let builders = [
+            QuadTreeBuilder::new(&bounds),
+            QuadTreeBuilder::new(&bounds).bucket_capacity(8),
+            QuadTreeBuilder::new(&bounds).wrapping(true),
+        ];
+        let positions: Vec<Point> = (0..300usize)
+            .map(|i| Point::new((i * 37 % 100) as f32 / 10.0, (i * 61 % 97) as f32 / 9.7))
+            .collect();
+        let test = Point::new(0.2, 4.4);
+        let rare = |i: &usize| i % 50 == 7;
+
+        for builder in builders.iter() {
+            let mut quadtree: QuadTree<usize> = builder.build();
+            for (i, position) in positions.iter().enumerate() {
+                quadtree.insert(i, *position);
+            }
+            let distance = |i: usize| {
+                if quadtree.is_wrapping() {
+                    let dx = (positions[i].x() - test.x()).abs();
+                    let dy = (positions[i].y() - test.y()).abs();
+                    dx.min(10.0 - dx).powi(2) + dy.min(10.0 - dy).powi(2)
+                } else {
+                    positions[i].squared_distance(&test)
+                }
+            };
+
+            // Act
+            let nearest = quadtree.nearest_where(test, rare);
+            let k_nearest = quadtree.k_nearest_where(test, 4, rare);
+            let all = quadtree.k_nearest_where(test, 100, rare);
+
+            // Assert
+            let mut expected: Vec<usize> = (0..positions.len()).filter(rare).collect();
+            expected.sort_by(|a, b| distance(*a).partial_cmp(&distance(*b)).unwrap());
+            assert_eq!(Some(&expected[0]), nearest);
+            assert_eq!(expected[..4].iter().collect::<Vec<_>>(), k_nearest);
+            assert_eq!(expected.len(), all.len());
+            assert_eq!(None, quadtree.nearest_where(test, |_| false));
+        }
+    }
+
+    #[test]
+    fn quadtree_farthest_and_reverse_nearest_match_brute_force() {
+        // Arrange
+        let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+        let builders = [
+            QuadTreeBuilder::new(&bounds),
+            QuadTreeBuilder::new(&bounds).bucket_capacity(8),
+            QuadTreeBuilder::new(&bounds).compressed(true),
+        ];
+        let positions: Vec<Point> = (0..300usize)
+            .map(|i| Point::new((i * 37 % 100) as f32 / 10.0, (i * 61 % 97) as f32 / 9.7))
+            .collect();
+        let tests = [
+            Point::new(0.0, 0.0),
+            Point::new(4.45, 5.05),
+            Point::new(9.9, 3.3),
+        ];
+
+        for builder in builders.iter() {
+            let mut quadtree: QuadTree<usize> = builder.build();
+            for (i, position) in positions.iter().enumerate() {
+                quadtree.insert(i, *position);
+            }
+
+            for test in tests.iter() {
+                for k in 1..=3 {
+                    // Act
+                    let farthest = quadtree.farthest_position(*test).unwrap();
+                    let mut reverse = quadtree.reverse_k_nearest(*test, k);
+
+                    // Assert
+                    let max = positions
+                        .iter()
+                        .map(|p| p.squared_distance(test))
+                        .fold(0.0, f32::max);
+                    assert_eq!(max, farthest.squared_distance(test));
+
+                    let mut expected: Vec<usize> = (0..positions.len())
+                        .filter(|i| {
+                            let p = positions[*i];
+                            let distance = p.squared_distance(test);
+                            positions
+                                .iter()
+                                .enumerate()
+                                .filter(|(j, other)| {
+                                    *j != *i && other.squared_distance(&p) <= distance
+                                })
+                                .count()
+                                < k
+                        })
+                        .collect();
+                    reverse.sort_unstable();
+                    expected.sort_unstable();
+                    assert_eq!(expected, reverse.into_iter().copied().collect::<Vec<_>>());
+                }
+            }
+        }
+        let empty: QuadTree<u8> = QuadTree::new_bounded(&bounds);
+        assert_eq!(None, empty.farthest((1.0, 1.0)));
+        assert!(empty.reverse_nearest((1.0, 1.0)).is_empty());
+    }
+
+    #[test]
+    fn quadtree_reverse_nearest_in_narrow_nodes_match_brute_force() {
+        // Arrange
+        let bounds = Rectangle::new((0.0, 0.0), 1.0, 16.0);
+        // the nodes of narrow bounds are tall, so nodes beside a test point reach far above and below it
+        let position_sets: [Vec<Point>; 2] = [
+            vec![
+                Point::new(0.8, 0.86),
+                Point::new(0.63, 1.27),
+                Point::new(0.93, 1.19),
+                Point::new(0.83, 0.99),
+                Point::new(0.46, 0.94),
+                Point::new(0.42, 3.48),
+            ],
+            (0..150usize)
+                .map(|i| Point::new((i * 37 % 100) as f32 / 100.0, (i * 61 % 157) as f32 / 9.8))
+                .collect(),
+        ];
+        let tests: Vec<Point> = (0..100usize)
+            .map(|i| Point::new((i * 13 % 97) as f32 / 97.0, (i * 29 % 389) as f32 / 24.3))
+            .chain(vec![Point::new(0.74, 1.06)])
+            .collect();
+
+        for positions in position_sets.iter() {
+            let mut quadtree: QuadTree<usize> = QuadTree::new_bounded(&bounds);
+            for (i, position) in positions.iter().enumerate() {
+                quadtree.insert(i, *position);
+            }
+
+            for test in tests.iter() {
+                for k in 1..=3 {
+                    // Act
+                    let mut reverse = quadtree.reverse_k_nearest(*test, k);
+
+                    // Assert
+                    let mut expected: Vec<usize> = (0..positions.len())
+                        .filter(|i| {
+                            let p = positions[*i];
+                            let distance = p.squared_distance(test);
+                            positions
+                                .iter()
+                                .enumerate()
+                                .filter(|(j, other)| {
+                                    *j != *i && other.squared_distance(&p) <= distance
+                                })
+                                .count()
+                                < k
+                        })
+                        .collect();
+                    reverse.sort_unstable();
+                    expected.sort_unstable();
+                    assert_eq!(expected, reverse.into_iter().copied().collect::<Vec<_>>());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn quadtree_segment_queries_match_brute_force() {
+        // Arrange
+        let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+        let builders = [
+            QuadTreeBuilder::new(&bounds),
+            QuadTreeBuilder::new(&bounds).bucket_capacity(8),
+            QuadTreeBuilder::new(&bounds).compressed(true),
+        ];
+        let positions: Vec<Point> = (0..300usize)
+            .map(|i| Point::new((i * 37 % 100) as f32 / 10.0, (i * 61 % 97) as f32 / 9.7))
+            .collect();
+        let segments = [
+            (Point::new(0.0, 0.0), Point::new(10.0, 10.0)),
+            (Point::new(-2.0, 3.3), Point::new(12.0, 3.3)),
+            (Point::new(7.1, 9.0), Point::new(1.2, 0.4)),
+            (Point::new(4.0, 4.0), Point::new(4.0, 4.0)),
+        ];
+        let projection = |pt: &Point, a: &Point, b: &Point| {
+            let (dx, dy) = (b.x() - a.x(), b.y() - a.y());
+            let length = dx * dx + dy * dy;
+            if length > 0.0 {
+                (((pt.x() - a.x()) * dx + (pt.y() - a.y()) * dy) / length).clamp(0.0, 1.0)
+            } else {
+                0.0
+            }
+        };
+        let distance_to_segment = |pt: &Point, a: &Point, b: &Point| {
+            let t = projection(pt, a, b);
+            let closest = Point::new(a.x() + t * (b.x() - a.x()), a.y() + t * (b.y() - a.y()));
+            pt.squared_distance(&closest)
+        };
+
+        for builder in builders.iter() {
+            let mut quadtree: QuadTree<usize> = builder.build();
+            for (i, position) in positions.iter().enumerate() {
+                quadtree.insert(i, *position);
+            }
+
+            for (a, b) in segments.iter() {
+                // Act
+                let found = quadtree.find_along_segment(*a, *b, 0.4);
+                let direction = Point::new(b.x() - a.x(), b.y() - a.y());
+                let cast = quadtree.raycast(*a, direction, a.squared_distance(b).sqrt(), 0.4);
+
+                // Assert
+                if a != b {
+                    assert_eq!(found, cast);
+                } else {
+                    assert!(cast.is_empty());
+                }
+                let along: Vec<f32> = found
+                    .iter()
+                    .map(|i| projection(&positions[**i], a, b))
+                    .collect();
+                assert!(along.windows(2).all(|w| w[0] <= w[1] + 1e-4));
+                let mut found: Vec<usize> = found.into_iter().copied().collect();
+                found.sort_unstable();
+                let expected: Vec<usize> = (0..positions.len())
+                    .filter(|i| distance_to_segment(&positions[*i], a, b) <= 0.4 * 0.4 - 1e-4)
+                    .collect();
+                assert!(expected.iter().all(|i| found.contains(i)));
+                assert!(found
+                    .iter()
+                    .all(|i| distance_to_segment(&positions[*i], a, b) <= 0.4 * 0.4 + 1e-4));
+            }
+        }
+    }
+
+    #[test]
+    fn quadtree_joins_ignore_wrapping() {
+        // Arrange
+        let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+        let mut customers: QuadTree<u8> = QuadTreeBuilder::new(&bounds).wrapping(true).build();
+        let mut depots: QuadTree<u8> = QuadTreeBuilder::new(&bounds).wrapping(true).build();
+        customers.insert(1, (0.5, 5.0));
+        depots.insert(2, (9.8, 5.0));
+        depots.insert(3, (2.0, 5.0));
+
+        // Act
+        let within = customers.join_within(&depots, 1.0);
+        let nearest = customers.join_nearest(&depots);
+
+        // Assert
+        assert!(within.is_empty());
+        assert_eq!(vec![(&1, &3)], nearest);
+        assert_eq!(Some(&2), depots.find_nearest_neighbor((0.5, 5.0)));
+    }
+
+    #[test]
+    fn quadtree_joins_match_nested_loops() {
+        // Arrange
+        let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+        let builders = [
+            QuadTreeBuilder::new(&bounds),
+            QuadTreeBuilder::new(&bounds).bucket_capacity(8),
+            QuadTreeBuilder::new(&bounds).compressed(true),
+        ];
+        let positions: Vec<Point> = (0..200usize)
+            .map(|i| Point::new((i * 37 % 100) as f32 / 10.0, (i * 61 % 97) as f32 / 9.7))
+            .collect();
+        let others: Vec<Point> = (0..150usize)
+            .map(|i| Point::new((i * 53 % 89) as f32 / 8.0, (i * 29 % 83) as f32 / 10.0))
+            .collect();
+        let mut other: QuadTree<usize> =
+            QuadTreeBuilder::new(&Rectangle::new((2.0, -1.0), 10.0, 10.0))
+                .bucket_capacity(4)
+                .build();
+        let others: Vec<Point> = others
+            .into_iter()
+            .filter(|position| other.bounds.contains(*position))
+            .collect();
+        for (i, position) in others.iter().enumerate() {
+            assert!(other.insert(i, *position));
+        }
+
+        for builder in builders.iter() {
+            let mut quadtree: QuadTree<usize> = builder.build();
+            for (i, position) in positions.iter().enumerate() {
+                quadtree.insert(i, *position);
+            }
+
+            // Act
+            let mut within: Vec<(usize, usize)> = quadtree
+                .join_within(&other, 0.6)
+                .into_iter()
+                .map(|(a, b)| (*a, *b))
+                .collect();
+            let nearest = quadtree.join_nearest(&other);
+
+            // Assert
+            let mut expected = Vec::new();
+            for (i, a) in positions.iter().enumerate() {
+                for (j, b) in others.iter().enumerate() {
+                    if a.squared_distance(b) <= 0.6 * 0.6 {
+                        expected.push((i, j));
+                    }
+                }
+            }
+            within.sort_unstable();
+            assert!(!expected.is_empty());
+            assert_eq!(expected, within);
+
+            assert_eq!(positions.len(), nearest.len());
+            for (a, b) in nearest {
+                let closest = others
+                    .iter()
+                    .map(|other| other.squared_distance(&positions[*a]))
+                    .fold(f32::INFINITY, f32::min);
+                assert_eq!(closest, others[*b].squared_distance(&positions[*a]));
+            }
+        }
+        assert!(QuadTree::<u8>::new_bounded(&bounds)
+            .join_nearest(&other)
+            .is_empty());
+        assert!(other
+            .join_nearest(&QuadTree::<u8>::new_bounded(&bounds))
+            .is_empty());
+    }
+
+    #[test]
+    fn quadtree_counts_match_found_data() {
+        // Arrange
+        let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+        let builders = [
+            QuadTreeBuilder::new(&bounds),
+            QuadTreeBuilder::new(&bounds).bucket_capacity(4),
+            QuadTreeBuilder::new(&bounds).compressed(true),
+            QuadTreeBuilder::new(&bounds).wrapping(true),
+        ];
+        let positions: Vec<Point> = (0..300usize)
+            .map(|i| Point::new((i * 37 % 100) as f32 / 10.0, (i * 61 % 97) as f32 / 9.7))
+            .collect();
+        let queries = [
+            Rectangle::new((0.0, 0.0), 10.0, 10.0),
+            Rectangle::new((2.5, 2.5), 5.0, 5.0),
+            Rectangle::new((1.3, 6.1), 2.2, 3.7),
+            Rectangle::new((-5.0, -5.0), 7.5, 20.0),
+            Rectangle::new((8.0, 8.0), 4.0, 4.0),
+            // circles wider than half of the tree overlap themselves when wrapping
+            Rectangle::new((-4.0, -3.0), 14.0, 14.0),
+        ];
+
+        for builder in builders.iter() {
+            let mut quadtree: QuadTree<usize> = builder.build();
+            for (i, position) in positions.iter().enumerate() {
+                quadtree.insert(i, *position);
+            }
+            for position in positions.iter().step_by(4) {
+                quadtree.remove(*position);
+            }
+            quadtree.retain(|_, data| *data % 7 != 0);
+            quadtree.remove_in_bounds(&Rectangle::new((6.0, 0.0), 2.0, 2.0));
+
+            for query in queries.iter() {
+                // Act
+                let count = quadtree.count_in_bounds(query);
+                let in_radius = quadtree.count_in_radius(query.center(), query.width() / 2.0);
+
+                // Assert
+                let found = quadtree.find_in_bounds(query).map_or(0, |data| data.len());
+                assert_eq!(found, count);
+                let found = quadtree
+                    .find_in_radius(query.center(), query.width() / 2.0)
+                    .map_or(0, |data| data.len());
+                assert_eq!(found, in_radius);
+            }
+            assert_eq!(
+                quadtree.len(),
+                quadtree.count_in_bounds(&Rectangle::new((0.0, 0.0), 10.0, 10.0))
+            );
+        }
+    }
+
+    #[test]
+    fn quadtree_is_send_and_sync() {
+        fn assert_send_sync<S: Send + Sync>() {}
+        assert_send_sync::<QuadTree<u8>>();
+        assert_send_sync::<QuadTree<String>>();
+    }
+
+    #[test]
+    fn quadtree_parallel_queries_match_sequential() {
+        // Arrange
+        let bounds: Rectangle = Rectangle::new((0.0, 0.0), 100.0, 100.0);
+        let mut quadtree: QuadTree<usize> = QuadTree::new_bounded(&bounds);
+        let mut index = 0;
+        for i in 0..40 {
+            for j in 0..40 {
+                quadtree.insert(index, (i as f32 * 2.5 + 0.1, j as f32 * 2.4 + 0.2));
+                index += 1;
+            }
+        }
+        let queries: Vec<Rectangle> = (0..20)
+            .map(|i| Rectangle::new((i as f32 * 4.0, i as f32 * 3.0), 15.0, 30.0))
+            .collect();
+        let points: Vec<Point> = (0..20)
+            .map(|i| Point::new(i as f32 * 5.0, 100.0 - i as f32 * 4.0))
+            .collect();
+
+        fn sorted(mut data: Vec<&usize>) -> Vec<&usize> {
+            data.sort();
+            data
+        }
+
+        // Assert
+        for query in queries.iter() {
+            assert_eq!(
+                sorted(quadtree.find_in_bounds(query).unwrap()),
+                sorted(quadtree.par_find_in_bounds(query).unwrap())
+            );
+        }
+        let many = quadtree.find_in_bounds_many(&queries);
+        for (query, found) in queries.iter().zip(many) {
+            assert_eq!(quadtree.find_in_bounds(query), found);
+        }
+        let nearest = quadtree.k_nearest_many(&points, 5);
+        for (pt, found) in points.iter().zip(nearest) {
+            assert_eq!(quadtree.k_nearest(*pt, 5), found);
+        }
+
+        let count = std::sync::atomic::AtomicUsize::new(0);
+        quadtree.par_for_each(|_, _| {
+            count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        });
+        assert_eq!(quadtree.len(), count.into_inner());
+    }
+
+    #[test]
+    fn quadtree_k_nearest_matches_brute_force() {
+        // Arrange
+        let bounds: Rectangle = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+        let mut quadtree: QuadTree<usize> = QuadTree::new_bounded(&bounds);
+        let mut positions: Vec<Point> = Vec::new();
+        for i in 0..200 {
+            let position = Point::new((i * 37 % 101) as f32 / 10.1, (i * 53 % 97) as f32 / 9.7);
+            if quadtree.insert(i, position) {
+                positions.push(position);
+            }
+        }
+        let pt = Point::new(4.2, 6.6);
+
+        // Act
+        let found = quadtree.k_nearest_positions(pt, 10);
+
+        // Assert
+        positions.sort_by(|a, b| {
+            a.squared_distance(&pt)
+                .partial_cmp(&b.squared_distance(&pt))
+                .unwrap()
+        });
+        let expected: Vec<f32> = positions[..10]
+            .iter()
+            .map(|p| p.squared_distance(&pt))
+            .collect();
+        let actual: Vec<f32> = found.iter().map(|p| p.squared_distance(&pt)).collect();
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn quadtree_nearest_neighbor_agrees_with_k_nearest() {
+        // Arrange
+        let bounds: Rectangle = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+        let mut quadtree: QuadTree<u8> = QuadTree::new_bounded(&bounds);
+        quadtree.insert(1, (0.5, 0.5));
+        quadtree.insert(2, (9.0, 9.0));
+        quadtree.insert(3, (4.9, 5.1));
+
+        for pt in [
+            Point::new(-1.0, -1.0),
+            Point::new(12.0, 8.0),
+            Point::new(5.0, 5.0),
+            Point::new(2.6, 2.6),
+        ]
+        .iter()
+        {
+            // Act
+            let nearest = quadtree.find_nearest_neighbor(*pt);
+            let position = quadtree.find_nearest_neighbor_position(*pt);
+
+            // Assert
+            assert_eq!(quadtree.k_nearest(*pt, 1).first().copied(), nearest);
+            assert_eq!(
+                quadtree.k_nearest_positions(*pt, 1).first().copied(),
+                position
+            );
+        }
+    }
+
+    #[test]
+    fn quadtree_mesh_is_conforming() {
+        // Arrange
+        let bounds: Rectangle = Rectangle::new((0.0, 0.0), 8.0, 8.0);
+        let mut quadtree: QuadTree<u8> = QuadTree::new_bounded(&bounds);
+        quadtree.insert(0, (3.9, 3.9));
+        quadtree.insert(1, (3.9, 3.8));
+        quadtree.insert(2, (6.1, 1.2));
+        quadtree.insert(3, (6.1, 1.3));
+        quadtree.balance();
+
+        // Act
+        let mesh = quadtree.mesh();
+
+        // Assert
+        let mut area = 0.0;
+        let mut edges = std::collections::HashMap::new();
+        for triangle in mesh.indices.chunks(3) {
+            let [a, b, c] = [
+                mesh.vertices[triangle[0] as usize],
+                mesh.vertices[triangle[1] as usize],
+                mesh.vertices[triangle[2] as usize],
+            ];
+            let signed_area =
+                ((b.x() - a.x()) * (c.y() - a.y()) - (c.x() - a.x()) * (b.y() - a.y())) / 2.0;
+            assert!(signed_area > 0.0);
+            area += signed_area;
+
+            for i in 0..3 {
+                let edge = (triangle[i], triangle[(i + 1) % 3]);
+                *edges.entry(edge).or_insert(0) += 1;
+            }
+        }
+        assert!((area - 64.0).abs() < 1e-3);
+
+        // every edge inside of the bounds is shared by two triangles in opposite directions
+        for ((a, b), count) in edges.iter() {
+            assert_eq!(1, *count);
+            let (pa, pb) = (mesh.vertices[*a as usize], mesh.vertices[*b as usize]);
+            let on_boundary = |f: fn(&Point) -> f32, v: f32| f(&pa) == v && f(&pb) == v;
+            if !(on_boundary(Point::x, 0.0)
+                || on_boundary(Point::x, 8.0)
+                || on_boundary(Point::y, 0.0)
+                || on_boundary(Point::y, 8.0))
+            {
+                assert!(edges.contains_key(&(*b, *a)));
+            }
+        }
     }
 }
@@ -0,0 +1,122 @@
+use crate::{point::Point, rectangle::Rectangle};
+
+/// A line segment with a width, starting at `origin` and going `length` along the unit vector `direction`.
+/// Positions are hit, if they are at most `width` away from the segment.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Ray {
+    origin: Point,
+    direction: (f32, f32),
+    length: f32,
+    width: f32,
+}
+
+impl Ray {
+    /// Creates a ray, `None` if the direction has no length or any argument is not finite
+    pub(crate) fn new(origin: Point, direction: Point, length: f32, width: f32) -> Option<Self> {
+        let norm = direction.x().hypot(direction.y());
+        if !(origin.is_finite() && norm.is_normal() && length.is_finite() && width.is_finite()) {
+            return None;
+        }
+
+        Some(Self {
+            origin,
+            direction: (direction.x() / norm, direction.y() / norm),
+            length: length.max(0.0),
+            width: width.max(0.0),
+        })
+    }
+
+    /// Creates a ray going from `a` to `b`, `None` if any argument is not finite.
+    /// If `a` and `b` are the same, this is a circle around `a`.
+    pub(crate) fn between(a: Point, b: Point, width: f32) -> Option<Self> {
+        let direction = Point::new(b.x() - a.x(), b.y() - a.y());
+        let length = direction.x().hypot(direction.y());
+        if length.is_normal() {
+            Self::new(a, direction, length, width)
+        } else {
+            Self::new(a, Point::new(1.0, 0.0), 0.0, width)
+        }
+    }
+
+    /// The distance along the ray to the closest point of the segment, if `pt` is hit
+    pub(crate) fn hit(&self, pt: &Point) -> Option<f32> {
+        let (vx, vy) = (pt.x() - self.origin.x(), pt.y() - self.origin.y());
+        let t = (vx * self.direction.0 + vy * self.direction.1).clamp(0.0, self.length);
+        let (dx, dy) = (vx - t * self.direction.0, vy - t * self.direction.1);
+        if dx * dx + dy * dy <= self.width * self.width {
+            Some(t)
+        } else {
+            None
+        }
+    }
+
+    /// Slab test of the segment against the rectangle grown by the width on all sides,
+    /// which is true for every rectangle containing a position that could be hit
+    pub(crate) fn crosses(&self, bounds: &Rectangle) -> bool {
+        let slabs = [
+            (
+                self.origin.x(),
+                self.direction.0,
+                bounds.min_x() - self.width,
+                bounds.max_x() + self.width,
+            ),
+            (
+                self.origin.y(),
+                self.direction.1,
+                bounds.min_y() - self.width,
+                bounds.max_y() + self.width,
+            ),
+        ];
+
+        let (mut t_min, mut t_max) = (0.0f32, self.length);
+        for (origin, direction, min, max) in slabs.iter() {
+            if *direction == 0.0 {
+                if origin < min || origin > max {
+                    return false;
+                }
+                continue;
+            }
+
+            let (a, b) = ((min - origin) / direction, (max - origin) / direction);
+            t_min = t_min.max(a.min(b));
+            t_max = t_max.min(a.max(b));
+            if t_min > t_max {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ray_should_hit_points_and_cross_rectangles() {
+        // Arrange
+        let ray = Ray::new(Point::new(0.0, 0.0), Point::new(2.0, 2.0), 5.0, 0.5).unwrap();
+
+        // Act
+        let on = ray.hit(&Point::new(2.0, 2.0));
+        let beside = ray.hit(&Point::new(2.0, 2.6));
+        let behind = ray.hit(&Point::new(-0.3, -0.3));
+        let beyond = ray.hit(&Point::new(4.0, 4.0));
+
+        // Assert
+        assert!((on.unwrap() - 8f32.sqrt()).abs() < 1e-5);
+        assert!(beside.is_some());
+        assert_eq!(Some(0.0), behind);
+        assert_eq!(None, beyond);
+        assert!(ray.crosses(&Rectangle::new((3.0, 0.0), 1.0, 3.2)));
+        assert!(!ray.crosses(&Rectangle::new((3.0, 0.0), 1.0, 1.5)));
+        assert!(!ray.crosses(&Rectangle::new((4.5, 4.5), 1.0, 1.0)));
+        assert!(Ray::new(Point::new(0.0, 0.0), Point::new(0.0, 0.0), 1.0, 0.0).is_none());
+        assert!(
+            Ray::between(Point::new(1.0, 1.0), Point::new(1.0, 1.0), 0.5)
+                .unwrap()
+                .hit(&Point::new(1.3, 1.3))
+                .is_some()
+        );
+    }
+}
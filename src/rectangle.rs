@@ -2,6 +2,8 @@ use crate::point::Point;
 
 /// A rectangle representation, anchored at the bottom left corner
 ///
+/// Whether points on the right and top edge are inside of the rectangle is given by it's [`Boundary`],
+/// the left and bottom edge are always inside.
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Rectangle {
     /// anchor of rectangle at bottom left corner
@@ -9,16 +11,87 @@ pub struct Rectangle {
 
     width: f32,
     height: f32,
+
+    boundary: Boundary,
+}
+
+/// Whether the right and top edge of a [`Rectangle`] belong to it.
+///
+/// For the bounds of a tree, this also decides which quadrant points exactly on the center lines
+/// of a node belong to, so every quadrant of a node has the same boundary as the node itself.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Boundary {
+    /// All edges belong to the rectangle, the default.
+    /// Points on the center lines of a node belong to the bottom and left quadrants.
+    Closed,
+
+    /// The right and top edges don't belong to the rectangle,
+    /// so rectangles tiling a plane never share a point.
+    /// Points on the center lines of a node belong to the top and right quadrants.
+    HalfOpen,
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub(crate) enum Quadrant {
+/// One of the four equally sized quarters of a node, see [`Rectangle`] for the orientation.
+/// Which quadrant points located exactly on the center lines of a node belong to depends on it's [`Boundary`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Quadrant {
+    /// The quarter containing the anchor of the node bounds
     BottomLeft,
+    /// The quarter right of the anchor
     BottomRight,
+    /// The quarter diagonally opposite of the anchor
     TopRight,
+    /// The quarter above the anchor
     TopLeft,
 }
 
+impl Quadrant {
+    /// All quadrants, in the order they are stored and visited inside of a node
+    pub const ALL: [Quadrant; 4] = [
+        Quadrant::BottomLeft,
+        Quadrant::BottomRight,
+        Quadrant::TopRight,
+        Quadrant::TopLeft,
+    ];
+
+    /// Create a quadrant from it's horizontal and vertical half, `0` is left / bottom, `1` is right / top
+    pub(crate) fn from_bits(x: u8, y: u8) -> Self {
+        match (x, y) {
+            (0, 0) => Quadrant::BottomLeft,
+            (1, 0) => Quadrant::BottomRight,
+            (1, 1) => Quadrant::TopRight,
+            (0, 1) => Quadrant::TopLeft,
+            _ => unreachable!("Quadrant bits have to be 0 or 1"),
+        }
+    }
+
+    /// The position of the quadrant in [`Quadrant::ALL`]
+    pub(crate) fn index(&self) -> usize {
+        match self {
+            Quadrant::BottomLeft => 0,
+            Quadrant::BottomRight => 1,
+            Quadrant::TopRight => 2,
+            Quadrant::TopLeft => 3,
+        }
+    }
+
+    /// The horizontal half of the quadrant, `0` is left, `1` is right
+    pub(crate) fn x_bit(&self) -> u8 {
+        match self {
+            Quadrant::BottomLeft | Quadrant::TopLeft => 0,
+            Quadrant::BottomRight | Quadrant::TopRight => 1,
+        }
+    }
+
+    /// The vertical half of the quadrant, `0` is bottom, `1` is top
+    pub(crate) fn y_bit(&self) -> u8 {
+        match self {
+            Quadrant::BottomLeft | Quadrant::BottomRight => 0,
+            Quadrant::TopRight | Quadrant::TopLeft => 1,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub(crate) enum RectangleRelation {
     /// Both rectangles are disjoint from another
@@ -54,6 +127,7 @@ impl Rectangle {
             anchor: anchor.into(),
             width,
             height,
+            boundary: Boundary::Closed,
         }
     }
 
@@ -79,9 +153,39 @@ impl Rectangle {
             anchor: Point::new(pt.x() - width / 2.0, pt.y() - height / 2.0),
             width,
             height,
+            boundary: Boundary::Closed,
         }
     }
 
+    /// Sets whether the right and top edge belong to the rectangle, see [`Boundary`]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::{Boundary, Rectangle};
+    /// let closed = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+    /// let half_open = closed.with_boundary(Boundary::HalfOpen);
+    ///
+    /// assert!(closed.contains((10.0, 5.0)));
+    /// assert!(!half_open.contains((10.0, 5.0)));
+    /// assert!(half_open.contains((0.0, 5.0)));
+    /// ```
+    pub fn with_boundary(mut self, boundary: Boundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    /// Whether the right and top edge belong to the rectangle, see [`Boundary`] and [`Rectangle::with_boundary`]
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    /// Test if the given point is inside of the rectangle, respecting it's [`Boundary`].
+    /// Points with NaN or infinite coordinates are never inside.
+    pub fn contains(&self, pt: impl Into<Point>) -> bool {
+        self.find_quadrant(&pt.into()).is_some()
+    }
+
     pub fn width(&self) -> f32 {
         self.width
     }
@@ -118,27 +222,41 @@ impl Rectangle {
     /// |    |:    |
     /// x----|:----|
     ///
+    /// Points with NaN or infinite coordinates are outside of every rectangle,
+    /// comparisons with NaN would otherwise always put them in the bottom left quadrant.
     pub(crate) fn find_quadrant(&self, pt: &Point) -> Option<Quadrant> {
         let min_x = self.min_x();
         let min_y = self.min_y();
         let max_x = self.max_x();
         let max_y = self.max_y();
 
+        if !pt.is_finite() {
+            return None;
+        }
+
         // Test if the point is inside the general bounds of the rectangle
-        if (pt.x() < min_x) || (pt.x() > max_x) || (pt.y() < min_y) || (pt.y() > max_y) {
+        let outside = match self.boundary {
+            Boundary::Closed => pt.x() > max_x || pt.y() > max_y,
+            Boundary::HalfOpen => pt.x() >= max_x || pt.y() >= max_y,
+        };
+        if outside || (pt.x() < min_x) || (pt.y() < min_y) {
             None
         } else {
             let mid_x = (min_x + max_x) / 2.0;
             let mid_y = (min_y + max_y) / 2.0;
+            let (right, top) = match self.boundary {
+                Boundary::Closed => (pt.x() > mid_x, pt.y() > mid_y),
+                Boundary::HalfOpen => (pt.x() >= mid_x, pt.y() >= mid_y),
+            };
 
-            if pt.x() > mid_x {
-                if pt.y() > mid_y {
+            if right {
+                if top {
                     Some(Quadrant::TopRight)
                 } else {
                     Some(Quadrant::BottomRight)
                 }
             } else {
-                if pt.y() > mid_y {
+                if top {
                     Some(Quadrant::TopLeft)
                 } else {
                     Some(Quadrant::BottomLeft)
@@ -150,24 +268,146 @@ impl Rectangle {
     pub(crate) fn create_quadrant(&self, quadrant: &Quadrant) -> Self {
         let width = self.width / 2.0;
         let height = self.height / 2.0;
-        let anchor: Point;
-
-        match quadrant {
-            Quadrant::BottomLeft => anchor = self.anchor.clone(),
-            Quadrant::BottomRight => anchor = Point::new(self.anchor.x() + width, self.anchor.y()),
-            Quadrant::TopRight => {
-                anchor = Point::new(self.anchor.x() + width, self.anchor.y() + height)
-            }
-            Quadrant::TopLeft => anchor = Point::new(self.anchor.x(), self.anchor.y() + height),
-        }
+        let anchor = match quadrant {
+            Quadrant::BottomLeft => self.anchor,
+            Quadrant::BottomRight => Point::new(self.anchor.x() + width, self.anchor.y()),
+            Quadrant::TopRight => Point::new(self.anchor.x() + width, self.anchor.y() + height),
+            Quadrant::TopLeft => Point::new(self.anchor.x(), self.anchor.y() + height),
+        };
 
         Self {
             anchor,
             width,
             height,
+            boundary: self.boundary,
+        }
+    }
+
+    /// Find the smallest cell containing all given points,
+    /// out of this rectangle and all cells created by repeatedly splitting it into quadrants.
+    /// All points have to be inside of this rectangle and must not all be equal.
+    pub(crate) fn common_cell(&self, points: &[Point]) -> Self {
+        let mut cell = *self;
+        loop {
+            let mut quadrants = points.iter().map(|pt| cell.find_quadrant(pt));
+            match quadrants.next() {
+                Some(Some(first)) if quadrants.all(|q| q == Some(first)) => {
+                    cell = cell.create_quadrant(&first)
+                }
+                _ => return cell,
+            }
         }
     }
 
+    /// Split this rectangle into it's parts inside of `space`, as if `space` wrapped around at it's edges
+    /// like a torus. Where the rectangle crosses an edge of `space`, it continues at the opposite edge.
+    pub(crate) fn wrap_into(&self, space: &Rectangle) -> Vec<Self> {
+        let axis = |min: f32, size: f32, space_min: f32, space_size: f32| -> Vec<(f32, f32)> {
+            if size >= space_size {
+                return vec![(space_min, space_size)];
+            }
+            let start = space_min + (min - space_min).rem_euclid(space_size);
+            let overflow = start + size - (space_min + space_size);
+            if overflow <= 0.0 {
+                vec![(start, size)]
+            } else {
+                vec![(start, size - overflow), (space_min, overflow)]
+            }
+        };
+
+        let xs = axis(self.min_x(), self.width, space.min_x(), space.width);
+        let ys = axis(self.min_y(), self.height, space.min_y(), space.height);
+        xs.iter()
+            .flat_map(|(x, width)| {
+                ys.iter().map(move |(y, height)| {
+                    Self::new((*x, *y), *width, *height).with_boundary(self.boundary)
+                })
+            })
+            .collect()
+    }
+
+    /// Move a point into this rectangle, as if it wrapped around at it's edges like a torus
+    pub(crate) fn wrap_point(&self, pt: &Point) -> Point {
+        Point::new(
+            self.min_x() + (pt.x() - self.min_x()).rem_euclid(self.width),
+            self.min_y() + (pt.y() - self.min_y()).rem_euclid(self.height),
+        )
+    }
+
+    /// The squared distance from the given point to the closest point of the rectangle,
+    /// which is 0 for points inside of the rectangle
+    pub(crate) fn squared_distance(&self, pt: &Point) -> f32 {
+        let dx = (self.min_x() - pt.x()).max(pt.x() - self.max_x()).max(0.0);
+        let dy = (self.min_y() - pt.y()).max(pt.y() - self.max_y()).max(0.0);
+        dx * dx + dy * dy
+    }
+
+    /// The squared distance between the closest points of two rectangles, zero if they overlap
+    pub(crate) fn squared_distance_to(&self, other: &Rectangle) -> f32 {
+        let dx = (other.min_x() - self.max_x())
+            .max(self.min_x() - other.max_x())
+            .max(0.0);
+        let dy = (other.min_y() - self.max_y())
+            .max(self.min_y() - other.max_y())
+            .max(0.0);
+        dx * dx + dy * dy
+    }
+
+    /// The squared distance from the given point to the farthest point of the rectangle
+    pub(crate) fn max_squared_distance(&self, pt: &Point) -> f32 {
+        let dx = (pt.x() - self.min_x())
+            .abs()
+            .max((self.max_x() - pt.x()).abs());
+        let dy = (pt.y() - self.min_y())
+            .abs()
+            .max((self.max_y() - pt.y()).abs());
+        dx * dx + dy * dy
+    }
+
+    /// The smallest rectangle with the given boundary containing all given finite points,
+    /// `None` if there are none
+    pub(crate) fn bounding<'a>(
+        points: impl IntoIterator<Item = &'a Point>,
+        boundary: Boundary,
+    ) -> Option<Self> {
+        let mut points = points.into_iter().filter(|pt| pt.is_finite());
+        let first = points.next()?;
+        let (mut min, mut max) = (*first, *first);
+        for pt in points {
+            min = Point::new(min.x().min(pt.x()), min.y().min(pt.y()));
+            max = Point::new(max.x().max(pt.x()), max.y().max(pt.y()));
+        }
+
+        let half_open = boundary == Boundary::HalfOpen;
+        Some(
+            Self::new(
+                min,
+                extent(min.x(), max.x(), half_open),
+                extent(min.y(), max.y(), half_open),
+            )
+            .with_boundary(boundary),
+        )
+    }
+
+    /// The smallest rectangle containing both this and the other rectangle,
+    /// with the boundary of this rectangle
+    pub(crate) fn union(&self, other: &Self) -> Self {
+        let min = Point::new(
+            self.min_x().min(other.min_x()),
+            self.min_y().min(other.min_y()),
+        );
+        let max = Point::new(
+            self.max_x().max(other.max_x()),
+            self.max_y().max(other.max_y()),
+        );
+        Self::new(
+            min,
+            extent(min.x(), max.x(), false),
+            extent(min.y(), max.y(), false),
+        )
+        .with_boundary(self.boundary)
+    }
+
     pub(crate) fn corners(&self) -> [Point; 4] {
         [
             (self.min_x(), self.min_y()).into(),
@@ -178,6 +418,15 @@ impl Rectangle {
     }
 
     pub(crate) fn relation(&self, other: &Rectangle) -> RectangleRelation {
+        // rectangles not overlapping on both axes can't share any points
+        if other.max_x() < self.min_x()
+            || other.min_x() > self.max_x()
+            || other.max_y() < self.min_y()
+            || other.min_y() > self.max_y()
+        {
+            return RectangleRelation::Disjoint;
+        }
+
         let inner_quadrants: Vec<Option<Quadrant>> = other
             .corners()
             .iter()
@@ -190,15 +439,15 @@ impl Rectangle {
             match self
                 .corners()
                 .iter()
-                .map(|c| other.find_quadrant(c))
-                .filter_map(|q| q)
+                .filter_map(|c| other.find_quadrant(c))
                 .count()
             {
-                // no corners from self inside of other
-                0 => RectangleRelation::Disjoint,
+                // no corners from self inside of other, but the rectangles overlap,
+                // so they are crossing each other
+                0 => RectangleRelation::Intersection,
 
                 // some corners inside of other
-                1 | 2 | 3 => RectangleRelation::Intersection,
+                1..=3 => RectangleRelation::Intersection,
 
                 // fully contained in other
                 4 => RectangleRelation::Containment(false),
@@ -218,6 +467,18 @@ impl Rectangle {
     }
 }
 
+/// The size of an interval starting at `min`, so it reaches `max`, or past it if `beyond` is set.
+/// Rounding can otherwise leave the far edge just short of `max`.
+fn extent(min: f32, max: f32, beyond: bool) -> f32 {
+    let mut size = max - min;
+    let mut step = f32::EPSILON * min.abs().max(max.abs()).max(f32::MIN_POSITIVE);
+    while min + size < max || (beyond && min + size <= max) {
+        size += step;
+        step *= 2.0;
+    }
+    size
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -260,6 +521,85 @@ mod test {
         assert_eq!(tl_rect, rect.create_quadrant(&Quadrant::TopLeft));
     }
 
+    #[test]
+    fn rectangle_should_bound_points() {
+        // Arrange
+        let points = [
+            Point::new(0.1, 3.3),
+            Point::new(0.7, -1.0),
+            Point::new(-2.5, 0.3),
+        ];
+
+        // Act
+        let bounds = Rectangle::bounding(points.iter(), Boundary::Closed).unwrap();
+        let half_open = Rectangle::bounding(points.iter(), Boundary::HalfOpen).unwrap();
+
+        // Assert
+        assert!(points.iter().all(|pt| bounds.contains(*pt)));
+        assert!(points.iter().all(|pt| half_open.contains(*pt)));
+        assert_eq!(-2.5, bounds.min_x());
+        assert_eq!(-1.0, bounds.min_y());
+        assert_eq!(Boundary::HalfOpen, half_open.boundary());
+        assert!(Rectangle::bounding(&[], Boundary::Closed).is_none());
+        assert!(Rectangle::bounding(&[Point::new(f32::NAN, 0.0)], Boundary::Closed).is_none());
+        assert_eq!(
+            Rectangle::new((-2.0, 0.0), 4.0, 3.0),
+            Rectangle::new((-2.0, 1.0), 1.0, 2.0).union(&Rectangle::new((0.0, 0.0), 2.0, 1.0))
+        );
+    }
+
+    #[test]
+    fn rectangle_should_wrap_into_space() {
+        // Arrange
+        let space = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+
+        // Act
+        let inside = Rectangle::new((2.0, 2.0), 3.0, 3.0).wrap_into(&space);
+        let corner = Rectangle::new((9.0, -1.0), 2.0, 3.0).wrap_into(&space);
+        let shifted = Rectangle::new((-18.0, 23.0), 2.0, 12.0).wrap_into(&space);
+
+        // Assert
+        assert_eq!(vec![Rectangle::new((2.0, 2.0), 3.0, 3.0)], inside);
+        assert_eq!(
+            vec![
+                Rectangle::new((9.0, 9.0), 1.0, 1.0),
+                Rectangle::new((9.0, 0.0), 1.0, 2.0),
+                Rectangle::new((0.0, 9.0), 1.0, 1.0),
+                Rectangle::new((0.0, 0.0), 1.0, 2.0),
+            ],
+            corner
+        );
+        assert_eq!(vec![Rectangle::new((2.0, 0.0), 2.0, 10.0)], shifted);
+    }
+
+    #[test]
+    fn rectangle_should_find_common_cell() {
+        // Arrange
+        let rect = Rectangle::new(Point::new(0.0, 0.0), 8.0, 8.0);
+
+        // Assert
+        assert_eq!(
+            Rectangle::new(Point::new(0.0, 0.0), 2.0, 2.0),
+            rect.common_cell(&[Point::new(0.5, 0.5), Point::new(1.5, 0.5)])
+        );
+        assert_eq!(
+            Rectangle::new(Point::new(6.0, 6.0), 0.5, 0.5),
+            rect.common_cell(&[Point::new(6.1, 6.1), Point::new(6.4, 6.3)])
+        );
+        assert_eq!(
+            rect,
+            rect.common_cell(&[Point::new(1.0, 1.0), Point::new(7.0, 7.0)])
+        );
+        assert_eq!(
+            Rectangle::new(Point::new(4.0, 4.0), 2.0, 2.0),
+            rect.common_cell(&[
+                Point::new(4.5, 4.5),
+                Point::new(5.5, 5.5),
+                Point::new(4.2, 5.8)
+            ])
+        );
+    }
+
     #[test]
     fn rectangle_should_find_correct_relations() {
         // Arrange
@@ -279,5 +619,9 @@ mod test {
 
         let other = Rectangle::new(Point::new(-1.0, -1.0), 8.0, 3.0);
         assert_eq!(RectangleRelation::Intersection, rect.relation(&other));
+
+        // crossing rectangles, without any corners inside of each other
+        let other = Rectangle::new(Point::new(2.0, -1.0), 1.0, 8.0);
+        assert_eq!(RectangleRelation::Intersection, rect.relation(&other));
     }
 }
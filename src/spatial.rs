@@ -19,7 +19,7 @@ use crate::point::Point;
 /// ⠀⠀⠛⢿⣿⣿⣿⣿⣿⣿⡿⠟⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀
 /// ⠀⠀⠀⠀⠀⠉⠉⠉⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct Spatial<T>
 where
     T: Sized,
@@ -43,6 +43,15 @@ where
     pub fn consume(self) -> T {
         self.data
     }
+
+    /// Gets the position and mutable access to the data, the position itself can't change
+    pub fn parts_mut(&mut self) -> (&Point, &mut T) {
+        (&self.position, &mut self.data)
+    }
+
+    pub fn into_parts(self) -> (Point, T) {
+        (self.position, self.data)
+    }
 }
 
 impl<T> From<(T, Point)> for Spatial<T>
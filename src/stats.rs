@@ -0,0 +1,121 @@
+use crate::{arena::Arena, node::NodeRef, TreeNode};
+
+/// Statistics about the shape of a [`QuadTree`](crate::QuadTree),
+/// as returned by [`QuadTree::stats`](crate::QuadTree::stats).
+///
+/// A *leaf* is any quadrant of a node that does not hold a child node,
+/// so it is either empty or holds stored data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeStats {
+    /// The number of nodes at each depth, the root is at depth 0
+    pub depth_histogram: Vec<usize>,
+
+    /// The number of all nodes in the tree, including the root
+    pub node_count: usize,
+
+    /// The number of leaf quadrants, empty or not
+    pub leaf_count: usize,
+
+    /// The number of leaf quadrants which don't hold any data
+    pub empty_quadrant_count: usize,
+
+    /// The average number of data points per leaf, including empty leaves
+    pub average_items_per_leaf: f32,
+
+    /// The maximum number of data points found in a single leaf
+    pub max_items_per_leaf: usize,
+
+    /// An estimate of the heap memory used by the tree structure, in bytes.
+    /// Heap memory owned by the stored data itself is not included.
+    pub heap_bytes: usize,
+}
+
+/// Walk all nodes stored in `arena`, starting at the root, and collect their [`TreeStats`]
+pub(crate) fn collect<T>(arena: &Arena<T>) -> TreeStats {
+    let mut stats = TreeStats {
+        depth_histogram: Vec::new(),
+        node_count: 0,
+        leaf_count: 0,
+        empty_quadrant_count: 0,
+        average_items_per_leaf: 0.0,
+        max_items_per_leaf: 0,
+        heap_bytes: 0,
+    };
+    let mut item_count: usize = 0;
+
+    visit(arena.root(), 0, &mut stats, &mut item_count);
+
+    if stats.leaf_count > 0 {
+        stats.average_items_per_leaf = item_count as f32 / stats.leaf_count as f32;
+    }
+    stats.heap_bytes = arena.heap_bytes();
+
+    stats
+}
+
+fn visit<T>(node: NodeRef<'_, T>, depth: usize, stats: &mut TreeStats, item_count: &mut usize) {
+    if stats.depth_histogram.len() <= depth {
+        stats.depth_histogram.push(0);
+    }
+    stats.depth_histogram[depth] += 1;
+    stats.node_count += 1;
+
+    for quadrant in node.quadrants() {
+        match quadrant {
+            None => {
+                stats.leaf_count += 1;
+                stats.empty_quadrant_count += 1;
+            }
+            Some(TreeNode::Point(_)) => {
+                stats.leaf_count += 1;
+                stats.max_items_per_leaf = stats.max_items_per_leaf.max(1);
+                *item_count += 1;
+            }
+            Some(TreeNode::Bucket(bucket)) => {
+                stats.leaf_count += 1;
+                stats.max_items_per_leaf = stats.max_items_per_leaf.max(bucket.len());
+                *item_count += bucket.len();
+            }
+            Some(TreeNode::Node(id)) => visit(node.get(*id), depth + 1, stats, item_count),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{node::Node, point::Point, rectangle::Rectangle};
+
+    #[test]
+    fn stats_of_empty_node() {
+        // Arrange
+        let arena: Arena<u8> = Arena::new_bounded(&Rectangle::new((0.0, 0.0), 10.0, 10.0));
+
+        // Act
+        let stats = collect(&arena);
+
+        // Assert
+        assert_eq!(vec![1], stats.depth_histogram);
+        assert_eq!(4, stats.empty_quadrant_count);
+        assert_eq!(0.0, stats.average_items_per_leaf);
+        assert_eq!(0, stats.heap_bytes);
+    }
+
+    #[test]
+    fn stats_should_count_arena_nodes() {
+        // Arrange
+        let mut arena: Arena<u8> = Arena::new_bounded(&Rectangle::new((0.0, 0.0), 10.0, 10.0));
+        arena.insert((0, Point::new(1.0, 1.0)).into());
+        arena.insert((1, Point::new(9.0, 9.0)).into());
+        arena.insert((2, Point::new(1.0, 2.0)).into());
+
+        // Act
+        let stats = collect(&arena);
+
+        // Assert
+        assert_eq!(vec![1, 1, 1], stats.depth_histogram);
+        assert_eq!(10, stats.leaf_count);
+        assert_eq!(0.3, stats.average_items_per_leaf);
+        assert!(stats.heap_bytes >= 2 * std::mem::size_of::<Node<u8>>());
+    }
+}
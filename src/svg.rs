@@ -0,0 +1,238 @@
+use crate::{point::Point, rectangle::Rectangle};
+use std::fmt::Write;
+
+/// A query shape that can be highlighted in a svg drawing of a [`QuadTree`](crate::QuadTree)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SvgQuery {
+    /// A rectangular query, as used by [`QuadTree::find_in_bounds`](crate::QuadTree::find_in_bounds)
+    Rectangle(Rectangle),
+
+    /// A circular query, as used by [`QuadTree::find_in_radius`](crate::QuadTree::find_in_radius)
+    Circle(Point, f32),
+}
+
+/// Options to control how [`QuadTree::to_svg`](crate::QuadTree::to_svg) draws a tree
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SvgOptions {
+    /// Width of the svg viewport in pixels
+    pub width: f32,
+
+    /// Height of the svg viewport in pixels
+    pub height: f32,
+
+    /// Empty space between the tree bounds and the viewport border, in pixels
+    pub padding: f32,
+
+    /// Radius of the circles drawn for stored points, in pixels
+    pub point_radius: f32,
+
+    /// An optional query, which will be drawn together with it's hits
+    pub query: Option<SvgQuery>,
+}
+
+impl SvgOptions {
+    /// Create new [`SvgOptions`] for the given viewport size
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quadtree::SvgOptions;
+    /// let options = SvgOptions::new(800.0, 600.0);
+    /// assert_eq!(800.0, options.width);
+    /// assert!(options.query.is_none());
+    /// ```
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            width,
+            height,
+            ..Default::default()
+        }
+    }
+
+    /// Highlight the given query, and all of it's hits in the drawing
+    pub fn with_query(mut self, query: SvgQuery) -> Self {
+        self.query = Some(query);
+        self
+    }
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            width: 512.0,
+            height: 512.0,
+            padding: 8.0,
+            point_radius: 2.0,
+            query: None,
+        }
+    }
+}
+
+/// Maps tree coordinates onto the svg viewport.
+/// Svg has it's y axis pointing down, so we flip it to keep the bottom left anchor at the bottom.
+struct Viewport {
+    bounds: Rectangle,
+    scale: f32,
+    offset_x: f32,
+    offset_y: f32,
+}
+
+impl Viewport {
+    fn new(bounds: &Rectangle, options: &SvgOptions) -> Self {
+        let inner_width = (options.width - 2.0 * options.padding).max(0.0);
+        let inner_height = (options.height - 2.0 * options.padding).max(0.0);
+        let scale_x = if bounds.width() > 0.0 {
+            inner_width / bounds.width()
+        } else {
+            1.0
+        };
+        let scale_y = if bounds.height() > 0.0 {
+            inner_height / bounds.height()
+        } else {
+            1.0
+        };
+        let scale = scale_x.min(scale_y);
+
+        // center the drawing inside of the viewport
+        Self {
+            bounds: *bounds,
+            scale,
+            offset_x: (options.width - bounds.width() * scale) / 2.0,
+            offset_y: (options.height - bounds.height() * scale) / 2.0,
+        }
+    }
+
+    fn x(&self, x: f32) -> f32 {
+        self.offset_x + (x - self.bounds.min_x()) * self.scale
+    }
+
+    fn y(&self, y: f32) -> f32 {
+        self.offset_y + (self.bounds.max_y() - y) * self.scale
+    }
+
+    fn length(&self, length: f32) -> f32 {
+        length * self.scale
+    }
+}
+
+/// Render node bounds, points and query hits into a svg document.
+/// All numbers are written with a fixed precision, so the output is deterministic.
+pub(crate) fn render<'tree>(
+    bounds: &Rectangle,
+    aabbs: &[&Rectangle],
+    points: impl Iterator<Item = &'tree Point>,
+    hits: &[Point],
+    options: &SvgOptions,
+) -> String {
+    let viewport = Viewport::new(bounds, options);
+    let mut svg = String::new();
+
+    // writing into a String can not fail, so we ignore the results below
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{:.3}" height="{:.3}" viewBox="0 0 {:.3} {:.3}">"#,
+        options.width, options.height, options.width, options.height
+    );
+
+    let _ = writeln!(svg, r#"<g class="nodes" fill="none" stroke="black">"#);
+    for aabb in aabbs {
+        write_rect(&mut svg, &viewport, aabb);
+    }
+    let _ = writeln!(svg, "</g>");
+
+    let _ = writeln!(svg, r#"<g class="points" fill="black">"#);
+    for pt in points {
+        write_point(&mut svg, &viewport, pt, options.point_radius);
+    }
+    let _ = writeln!(svg, "</g>");
+
+    if let Some(query) = &options.query {
+        let _ = writeln!(svg, r#"<g class="query" fill="none" stroke="red">"#);
+        match query {
+            SvgQuery::Rectangle(rect) => write_rect(&mut svg, &viewport, rect),
+            SvgQuery::Circle(center, radius) => {
+                let _ = writeln!(
+                    svg,
+                    r#"<circle cx="{:.3}" cy="{:.3}" r="{:.3}"/>"#,
+                    viewport.x(center.x()),
+                    viewport.y(center.y()),
+                    viewport.length(*radius)
+                );
+            }
+        }
+        let _ = writeln!(svg, "</g>");
+
+        let _ = writeln!(svg, r#"<g class="hits" fill="red">"#);
+        for pt in hits {
+            write_point(&mut svg, &viewport, pt, options.point_radius);
+        }
+        let _ = writeln!(svg, "</g>");
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn write_rect(svg: &mut String, viewport: &Viewport, rect: &Rectangle) {
+    let _ = writeln!(
+        svg,
+        r#"<rect x="{:.3}" y="{:.3}" width="{:.3}" height="{:.3}"/>"#,
+        viewport.x(rect.min_x()),
+        viewport.y(rect.max_y()),
+        viewport.length(rect.width()),
+        viewport.length(rect.height())
+    );
+}
+
+fn write_point(svg: &mut String, viewport: &Viewport, pt: &Point, radius: f32) {
+    let _ = writeln!(
+        svg,
+        r#"<circle cx="{:.3}" cy="{:.3}" r="{:.3}"/>"#,
+        viewport.x(pt.x()),
+        viewport.y(pt.y()),
+        radius
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn viewport_should_flip_y_axis() {
+        // Arrange
+        let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+        let mut options = SvgOptions::new(100.0, 100.0);
+        options.padding = 0.0;
+        let viewport = Viewport::new(&bounds, &options);
+
+        // Assert
+        assert_eq!(0.0, viewport.x(0.0));
+        assert_eq!(100.0, viewport.y(0.0));
+        assert_eq!(0.0, viewport.y(10.0));
+        assert_eq!(50.0, viewport.length(5.0));
+    }
+
+    #[test]
+    fn render_should_only_draw_query_when_given() {
+        // Arrange
+        let bounds = Rectangle::new((0.0, 0.0), 10.0, 10.0);
+        let points = [Point::new(1.0, 1.0)];
+        let options = SvgOptions::default();
+
+        // Act
+        let plain = render(&bounds, &[&bounds], points.iter(), &[], &options);
+        let query = render(
+            &bounds,
+            &[&bounds],
+            points.iter(),
+            &points,
+            &options.with_query(SvgQuery::Circle(Point::new(1.0, 1.0), 2.0)),
+        );
+
+        // Assert
+        assert!(!plain.contains("query"));
+        assert!(query.contains(r#"<g class="query""#));
+        assert_eq!(1, query.matches(r#"<g class="hits""#).count());
+    }
+}
@@ -1,18 +1,81 @@
-use crate::{node::Node, rectangle::Quadrant, spatial::Spatial, TreeNode};
+use crate::{
+    cursor::Cursor, node::NodeRef, point::Point, rectangle::Quadrant, spatial::Spatial, TreeNode,
+};
+
+/// Controls how a [`TreeVisitor`] walk continues after visiting a node or data point
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum VisitControl {
+    /// Continue the walk as usual
+    Continue,
+
+    /// Don't descend into the current node, continue with it's next sibling.
+    /// When returned for a data point, this is the same as [`VisitControl::Continue`].
+    SkipSubtree,
+
+    /// Stop the walk immediately
+    Stop,
+}
+
+/// A public, read-only visitor for custom traversals of a [`QuadTree`](crate::QuadTree),
+/// see [`QuadTree::walk`](crate::QuadTree::walk).
+///
+/// Nodes are visited depth-first, starting at the root.
+/// The quadrants of every node are visited in the order of [`Quadrant::ALL`],
+/// data points stored directly in a quadrant are visited in place of a child node.
+pub trait TreeVisitor<T> {
+    /// Called for every visited node, before any of it's quadrants
+    fn visit_node(&mut self, cursor: &Cursor<'_, T>) -> VisitControl;
+
+    /// Called for every visited data point, does nothing by default
+    fn visit_item(&mut self, _position: &Point, _data: &T) -> VisitControl {
+        VisitControl::Continue
+    }
+}
+
+/// Walk the tree below the node the cursor points at with the given visitor.
+/// Returns `false` if the visitor requested to stop.
+pub(crate) fn walk_cursor<T, V: TreeVisitor<T> + ?Sized>(
+    visitor: &mut V,
+    cursor: &mut Cursor<'_, T>,
+) -> bool {
+    match visitor.visit_node(cursor) {
+        VisitControl::Stop => return false,
+        VisitControl::SkipSubtree => return true,
+        VisitControl::Continue => (),
+    }
+
+    for quadrant in Quadrant::ALL.iter() {
+        if cursor.move_to_child(quadrant) {
+            let keep_going = walk_cursor(visitor, cursor);
+            cursor.move_to_parent();
+            if !keep_going {
+                return false;
+            }
+        } else {
+            for (position, data) in cursor.quadrant_items(*quadrant) {
+                if visitor.visit_item(position, data) == VisitControl::Stop {
+                    return false;
+                }
+            }
+        }
+    }
+
+    true
+}
 
 pub(crate) trait Visitor<T>
 where
     T: Sized,
 {
-    fn visit_quadrant(&mut self, quadrant: &Option<TreeNode<T>>) {
-        walk_quadrant(self, quadrant);
+    fn visit_quadrant(&mut self, parent: NodeRef<'_, T>, quadrant: &Option<TreeNode<T>>) {
+        walk_quadrant(self, parent, quadrant);
     }
 
-    fn visit_tree_node(&mut self, tree_node: &TreeNode<T>) {
-        walk_tree_node(self, tree_node);
+    fn visit_tree_node(&mut self, parent: NodeRef<'_, T>, tree_node: &TreeNode<T>) {
+        walk_tree_node(self, parent, tree_node);
     }
 
-    fn visit_node(&mut self, node: &Node<T>) {
+    fn visit_node(&mut self, node: NodeRef<'_, T>) {
         walk_node(self, node);
     }
 
@@ -23,26 +86,31 @@ where
 
 pub(crate) fn walk_quadrant<T, V: Visitor<T> + ?Sized>(
     visitor: &mut V,
+    parent: NodeRef<'_, T>,
     quadrant: &Option<TreeNode<T>>,
 ) {
-    match quadrant {
-        Some(tn) => visitor.visit_tree_node(tn),
-        None => (),
+    if let Some(tn) = quadrant {
+        visitor.visit_tree_node(parent, tn)
     }
 }
 
-pub(crate) fn walk_tree_node<T, V: Visitor<T> + ?Sized>(visitor: &mut V, tree_node: &TreeNode<T>) {
+pub(crate) fn walk_tree_node<T, V: Visitor<T> + ?Sized>(
+    visitor: &mut V,
+    parent: NodeRef<'_, T>,
+    tree_node: &TreeNode<T>,
+) {
     match tree_node {
         TreeNode::Point(pt) => visitor.visit_point(pt),
-        TreeNode::Node(n) => visitor.visit_node(n),
+        TreeNode::Bucket(bucket) => bucket.items().iter().for_each(|pt| visitor.visit_point(pt)),
+        TreeNode::Node(id) => visitor.visit_node(parent.get(*id)),
     }
 }
 
-pub(crate) fn walk_node<T, V: Visitor<T> + ?Sized>(visitor: &mut V, node: &Node<T>) {
-    visitor.visit_quadrant(node.quadrant(&Quadrant::BottomLeft));
-    visitor.visit_quadrant(node.quadrant(&Quadrant::BottomRight));
-    visitor.visit_quadrant(node.quadrant(&Quadrant::TopRight));
-    visitor.visit_quadrant(node.quadrant(&Quadrant::TopLeft));
+pub(crate) fn walk_node<T, V: Visitor<T> + ?Sized>(visitor: &mut V, node: NodeRef<'_, T>) {
+    visitor.visit_quadrant(node, node.quadrant(&Quadrant::BottomLeft));
+    visitor.visit_quadrant(node, node.quadrant(&Quadrant::BottomRight));
+    visitor.visit_quadrant(node, node.quadrant(&Quadrant::TopRight));
+    visitor.visit_quadrant(node, node.quadrant(&Quadrant::TopLeft));
 }
 
 pub(crate) fn walk_point<T, V: Visitor<T> + ?Sized>(_visitor: &mut V, _point: &Spatial<T>) {}
@@ -51,22 +119,22 @@ pub(crate) struct TwoWayVisitor<'qt, T>
 where
     T: Sized,
 {
-    parent: &'qt Node<T>,
+    parent: NodeRef<'qt, T>,
 }
 
 impl<'qt, T> Visitor<T> for TwoWayVisitor<'qt, T>
 where
     T: Sized,
 {
-    fn visit_quadrant(&mut self, quadrant: &Option<TreeNode<T>>) {
-        walk_quadrant(self, quadrant);
+    fn visit_quadrant(&mut self, parent: NodeRef<'_, T>, quadrant: &Option<TreeNode<T>>) {
+        walk_quadrant(self, parent, quadrant);
     }
 
-    fn visit_tree_node(&mut self, tree_node: &TreeNode<T>) {
-        walk_tree_node(self, tree_node);
+    fn visit_tree_node(&mut self, parent: NodeRef<'_, T>, tree_node: &TreeNode<T>) {
+        walk_tree_node(self, parent, tree_node);
     }
 
-    fn visit_node(&mut self, node: &Node<T>) {
+    fn visit_node(&mut self, node: NodeRef<'_, T>) {
         walk_node(self, node);
     }
 